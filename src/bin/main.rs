@@ -1,8 +1,11 @@
 use anyhow::{Context, Result};
-use boardjudge_backend::Config;
-use clap::{ArgEnum, Parser};
+use boardjudge_backend::{cli, migration, Config};
+use chrono::DateTime;
+use clap::{ArgEnum, Parser, Subcommand};
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use tracing::Level;
+use uuid::Uuid;
 
 #[derive(Parser, Serialize, Deserialize)]
 #[clap(author, version, about)]
@@ -11,6 +14,139 @@ struct Args {
     config: String,
     #[clap(short, long, arg_enum, default_value = "error")]
     level: ArgsLevel,
+    /// Apply pending migrations before serving
+    #[clap(long, global = true)]
+    migrate: bool,
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Serialize, Deserialize)]
+enum Command {
+    /// Run the web server, which is also done without a subcommand
+    Serve,
+    /// Manage database migrations
+    Migrate {
+        #[clap(subcommand)]
+        action: MigrateAction,
+    },
+    /// Manage users
+    User {
+        #[clap(subcommand)]
+        action: UserAction,
+    },
+    /// Import and export contest archives
+    Contest {
+        #[clap(subcommand)]
+        action: ContestAction,
+    },
+    /// Rejudge records on a local judger and wait for their verdicts
+    Rejudge {
+        ids: Vec<Uuid>,
+        /// Judge against this testdata version instead of the current one
+        #[clap(long)]
+        testdata: Option<String>,
+    },
+    /// Judge a local source against a problem without creating a record
+    JudgeFile {
+        #[clap(short, long)]
+        problem: Uuid,
+        /// `c` or `cxx`, guessed from the extension by default
+        #[clap(long)]
+        language: Option<String>,
+        file: PathBuf,
+    },
+    /// Judge local sources against a problem directory, without the server or the database
+    Judge {
+        /// A directory with `problem.json` and the testdata, as in a native package
+        problem: PathBuf,
+        /// Sources with an `expect: <status>` comment fail the command on another verdict
+        #[clap(required = true)]
+        files: Vec<PathBuf>,
+        /// `c` or `cxx`, guessed from the extension by default
+        #[clap(long)]
+        language: Option<String>,
+    },
+    /// Inspect the configuration
+    Config {
+        #[clap(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand, Serialize, Deserialize)]
+enum UserAction {
+    /// Create a user, the password is read from stdin if not given
+    Create {
+        name: String,
+        #[clap(long)]
+        nick: Option<String>,
+        #[clap(long)]
+        password: Option<String>,
+        #[clap(long)]
+        root: bool,
+    },
+    /// Set the password of a user, it is read from stdin if not given
+    ResetPassword {
+        name: String,
+        #[clap(long)]
+        password: Option<String>,
+    },
+    /// Make a user root
+    GrantRoot {
+        name: String,
+        /// Take root away instead
+        #[clap(long)]
+        revoke: bool,
+    },
+}
+
+#[derive(Subcommand, Serialize, Deserialize)]
+enum ContestAction {
+    /// Import a contest archive
+    Import {
+        file: PathBuf,
+        /// Map a user name of the archive to a local one, as `archived=local`
+        #[clap(long = "user")]
+        users: Vec<String>,
+    },
+    /// Export a contest archive
+    Export {
+        id: Uuid,
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+        /// Include records and their outputs
+        #[clap(long)]
+        records: bool,
+    },
+}
+
+#[derive(Subcommand, Serialize, Deserialize)]
+enum ConfigAction {
+    /// Check the database, storage, compilers and sandbox
+    Check,
+    /// Print the configuration with secrets redacted
+    Print {
+        /// Include environment overrides and secrets files, as the server sees them
+        #[clap(long)]
+        effective: bool,
+    },
+}
+
+#[derive(Subcommand, Serialize, Deserialize)]
+enum MigrateAction {
+    /// List migrations and whether they are applied
+    Status,
+    /// Apply pending migrations
+    Up {
+        #[clap(short, long)]
+        num: Option<usize>,
+    },
+    /// Roll back applied migrations
+    Down {
+        #[clap(short, long, default_value = "1")]
+        num: usize,
+    },
 }
 
 #[derive(ArgEnum, Clone, Copy, Serialize, Deserialize)]
@@ -26,8 +162,10 @@ pub enum ArgsLevel {
 async fn main() -> Result<()> {
     use ArgsLevel::*;
     let args = Args::parse();
-    let direct = std::fs::read_to_string(&args.config).context("failed to read config")?;
-    let xonfig = toml::from_str::<Config>(&direct).context("failed to parse config")?;
+    let xonfig = match args.command {
+        Some(Command::Judge { .. }) => Config::load_offline(Path::new(&args.config))?,
+        _ => Config::load(Path::new(&args.config))?,
+    };
     let fmt = tracing_subscriber::fmt();
     match args.level {
         Trace => fmt.with_max_level(Level::TRACE).with_test_writer().init(),
@@ -36,10 +174,208 @@ async fn main() -> Result<()> {
         Warn => fmt.with_max_level(Level::WARN).init(),
         Error => fmt.with_max_level(Level::ERROR).init(),
     };
-    let mut dir = std::env::current_dir().context("failed to read current dir")?;
+    let cwd = std::env::current_dir().context("failed to read current dir")?;
+    let mut dir = cwd.clone();
     dir.push(&args.config);
     dir.pop();
     std::env::set_current_dir(dir).context("failed to set current dir")?;
-    boardjudge_backend::main(xonfig).await?;
+    boardjudge_backend::init(xonfig);
+    match args.command {
+        None | Some(Command::Serve) => boardjudge_backend::main(args.migrate).await?,
+        Some(Command::Migrate { action }) => migrate(action).await?,
+        Some(Command::User { action }) => user(action).await?,
+        Some(Command::Contest { action }) => contest(&cwd, action).await?,
+        Some(Command::Rejudge { ids, testdata }) => {
+            let conn = boardjudge_backend::connect().await?;
+            for (id, status) in cli::rejudge(&conn, &ids, testdata).await? {
+                println!("{}\t{}", id, status);
+            }
+        }
+        Some(Command::JudgeFile {
+            problem,
+            language,
+            file,
+        }) => {
+            let file = cwd.join(file);
+            let language = language_of(&file, language)?;
+            let code = std::fs::read_to_string(&file).context("failed to read source")?;
+            let conn = boardjudge_backend::connect().await?;
+            let (status, tests) = cli::judge_file(&conn, problem, language, code).await?;
+            cli::print_tests(&tests);
+            println!("{}", Into::<&'static str>::into(status));
+        }
+        Some(Command::Judge {
+            problem,
+            files,
+            language,
+        }) => judge(&cwd.join(problem), &cwd, files, language).await?,
+        Some(Command::Config {
+            action: ConfigAction::Check,
+        }) => {
+            let checks = cli::config_check().await;
+            for (name, result) in checks.iter() {
+                match result {
+                    Ok(()) => println!("{}\tok", name),
+                    Err(e) => println!("{}\t{}", name, e),
+                }
+            }
+            if checks.values().any(|x| x.is_err()) {
+                anyhow::bail!("the configuration is not usable");
+            }
+        }
+        Some(Command::Config {
+            action: ConfigAction::Print { effective },
+        }) => {
+            let mut value = match effective {
+                true => toml::Value::try_from(boardjudge_backend::config())?,
+                false => Config::read(&cwd.join(&args.config))?,
+            };
+            boardjudge_backend::redact(&mut value);
+            print!("{}", toml::to_string(&value)?);
+        }
+    }
+    Ok(())
+}
+
+fn language_of(file: &Path, language: Option<String>) -> Result<String> {
+    match language {
+        Some(language) => Ok(language),
+        None => Ok(cli::language_of(&file.to_string_lossy())
+            .context("cannot guess the language, pass `--language`")?
+            .to_string()),
+    }
+}
+
+async fn judge(
+    problem: &Path,
+    cwd: &Path,
+    files: Vec<PathBuf>,
+    language: Option<String>,
+) -> Result<()> {
+    let mut mismatches = vec![];
+    for file in files {
+        let file = cwd.join(file);
+        let language = language_of(&file, language.clone())?;
+        let code = std::fs::read_to_string(&file)
+            .with_context(|| format!("failed to read {}", file.display()))?;
+        let expected = cli::expected_of(&code)
+            .with_context(|| format!("bad annotation in {}", file.display()))?;
+        let (status, tests) = cli::judge_local(problem, language, code).await?;
+        println!("== {}", file.display());
+        cli::print_tests(&tests);
+        let status: &'static str = status.into();
+        match expected.map(Into::<&'static str>::into) {
+            Some(expected) if expected != status => {
+                println!("{}, expected {}", status, expected);
+                mismatches.push(file);
+            }
+            _ => println!("{}", status),
+        }
+        println!();
+    }
+    if !mismatches.is_empty() {
+        for file in mismatches.iter() {
+            eprintln!("mismatch: {}", file.display());
+        }
+        anyhow::bail!("{} verdicts did not match", mismatches.len());
+    }
+    Ok(())
+}
+
+fn read_password(password: Option<String>) -> Result<String> {
+    if let Some(password) = password {
+        return Ok(password);
+    }
+    let mut line = String::new();
+    std::io::stdin()
+        .read_line(&mut line)
+        .context("failed to read password")?;
+    let password = line.trim_end_matches(['\r', '\n']).to_string();
+    if password.is_empty() {
+        anyhow::bail!("the password is empty");
+    }
+    Ok(password)
+}
+
+async fn user(action: UserAction) -> Result<()> {
+    let conn = boardjudge_backend::connect().await?;
+    match action {
+        UserAction::Create {
+            name,
+            nick,
+            password,
+            root,
+        } => {
+            let password = read_password(password)?;
+            let nick = nick.unwrap_or_else(|| name.clone());
+            let id = cli::user_create(&conn, name, nick, password, root).await?;
+            println!("{}", id);
+        }
+        UserAction::ResetPassword { name, password } => {
+            let password = read_password(password)?;
+            cli::user_update(&conn, &name, Some(password), None).await?;
+        }
+        UserAction::GrantRoot { name, revoke } => {
+            cli::user_update(&conn, &name, None, Some(!revoke)).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn contest(cwd: &Path, action: ContestAction) -> Result<()> {
+    let conn = boardjudge_backend::connect().await?;
+    match action {
+        ContestAction::Import { file, users } => {
+            let file = std::fs::read(cwd.join(file)).context("failed to read archive")?;
+            let users = users
+                .iter()
+                .map(|x| {
+                    x.split_once('=')
+                        .map(|(a, b)| (a.to_string(), b.to_string()))
+                        .with_context(|| format!("`{}` is not `archived=local`", x))
+                })
+                .collect::<Result<_>>()?;
+            let feedback = cli::contest_import(&conn, file, users).await?;
+            println!("{}", feedback["id"].as_str().unwrap_or_default());
+            for conflict in feedback["conflicts"].as_array().into_iter().flatten() {
+                eprintln!("conflict: {}", conflict.as_str().unwrap_or_default());
+            }
+        }
+        ContestAction::Export {
+            id,
+            output,
+            records,
+        } => {
+            let data = cli::contest_export(&conn, id, records).await?;
+            let output = cwd.join(output.unwrap_or_else(|| format!("{}.zip", id).into()));
+            std::fs::write(&output, data).context("failed to write archive")?;
+            println!("{}", output.display());
+        }
+    }
+    Ok(())
+}
+
+async fn migrate(action: MigrateAction) -> Result<()> {
+    let conn = boardjudge_backend::connect().await?;
+    match action {
+        MigrateAction::Status => {
+            for (name, applied) in migration::status(&conn).await? {
+                match applied.and_then(|x| DateTime::from_timestamp(x, 0)) {
+                    Some(time) => println!("{}\tapplied at {}", name, time),
+                    None => println!("{}\tpending", name),
+                }
+            }
+        }
+        MigrateAction::Up { num } => {
+            for name in migration::up(&conn, num).await? {
+                println!("applied {}", name);
+            }
+        }
+        MigrateAction::Down { num } => {
+            for name in migration::down(&conn, num).await? {
+                println!("rolled back {}", name);
+            }
+        }
+    }
     Ok(())
 }
@@ -0,0 +1,261 @@
+use crate::judger::{judge, judge_dir, Judge, Judger, Kind, Status, Tests};
+use crate::package::native::manifest;
+use crate::sys::schema::*;
+use crate::web::api;
+use crate::web::api::auth::Principal;
+use crate::web::api::submit::judge_of;
+use crate::web::api::testdata::commit;
+use crate::web::health;
+use crate::web::params::Params;
+use crate::web::prelude::AppResult;
+use anyhow::{Context, Result};
+use axum::body::HttpBody;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use sea_orm::prelude::*;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+use uuid::Uuid;
+
+// the console acts as root, audit logs attribute its actions to the nil user
+pub fn principal() -> Principal {
+    Principal {
+        user: user::Model {
+            id: Uuid::nil().to_string(),
+            name: "console".to_string(),
+            nick: "console".to_string(),
+            description: String::new(),
+            password: vec![],
+            banned: false,
+            root: true,
+        },
+        scope: None,
+    }
+}
+
+// runs a handler result through its response, exactly as a client would see it
+async fn call<R: IntoResponse>(result: AppResult<R>) -> Result<Vec<u8>> {
+    let response = result.into_response();
+    let status = response.status();
+    let mut body = response.into_body();
+    let mut data = vec![];
+    while let Some(chunk) = body.data().await {
+        data.extend_from_slice(&chunk?);
+    }
+    if !status.is_success() {
+        anyhow::bail!("{} {}", status, String::from_utf8_lossy(&data));
+    }
+    Ok(data)
+}
+
+pub async fn find_user(conn: &DatabaseConnection, name: &str) -> Result<user::Model> {
+    user::Entity::find()
+        .filter(user::Column::Name.eq(name))
+        .one(conn)
+        .await?
+        .with_context(|| format!("no user is named `{}`", name))
+}
+
+pub async fn user_create(
+    conn: &DatabaseConnection,
+    name: String,
+    nick: String,
+    password: String,
+    root: bool,
+) -> Result<Uuid> {
+    let payload = api::user::UserCreatePayload {
+        name,
+        nick,
+        description: String::new(),
+        password,
+        banned: false,
+        root,
+    };
+    let data =
+        call(api::user::user_create(Json(payload), Extension(conn.clone()), principal()).await)
+            .await?;
+    let feedback: Value = serde_json::from_slice(&data)?;
+    Ok(Uuid::from_str(feedback["id"].as_str().unwrap_or_default())?)
+}
+
+pub async fn user_update(
+    conn: &DatabaseConnection,
+    name: &str,
+    password: Option<String>,
+    root: Option<bool>,
+) -> Result<()> {
+    let model = find_user(conn, name).await?;
+    let payload = api::user::UserUpdatePayload {
+        id: Uuid::from_str(&model.id)?,
+        name: None,
+        nick: None,
+        description: None,
+        password,
+        banned: None,
+        root,
+    };
+    call(api::user::user_update(Json(payload), Extension(conn.clone()), principal()).await).await?;
+    Ok(())
+}
+
+pub async fn contest_import(
+    conn: &DatabaseConnection,
+    file: Vec<u8>,
+    users: BTreeMap<String, String>,
+) -> Result<Value> {
+    let payload = api::contest::ContestImportPayload { users };
+    let result = api::contest::import(conn, &principal(), payload, file).await;
+    let data = call(result.map(Json)).await?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+pub async fn contest_export(conn: &DatabaseConnection, id: Uuid, records: bool) -> Result<Vec<u8>> {
+    let payload = api::contest::ContestExportPayload { id, records };
+    call(api::contest::contest_export(Params(payload), Extension(conn.clone()), principal()).await)
+        .await
+}
+
+// queues the rejudges on a local judger and waits until every record leaves `waiting`
+pub async fn rejudge(
+    conn: &DatabaseConnection,
+    ids: &[Uuid],
+    testdata: Option<String>,
+) -> Result<Vec<(Uuid, String)>> {
+    let judger = Judger::daemon();
+    for id in ids {
+        let payload = api::record::RecordRejudgePayload {
+            id: *id,
+            testdata: testdata.clone(),
+        };
+        call(
+            api::record::record_rejudge(
+                Json(payload),
+                Extension(conn.clone()),
+                Extension(judger.clone()),
+                principal(),
+            )
+            .await,
+        )
+        .await
+        .with_context(|| format!("failed to rejudge `{}`", id))?;
+    }
+    let mut result = vec![];
+    for id in ids {
+        loop {
+            let model = record::Entity::find_by_id(id.to_string())
+                .one(conn)
+                .await?
+                .context("the record is gone")?;
+            if model.status != "waiting" {
+                result.push((*id, model.status));
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    }
+    Ok(result)
+}
+
+// judges a source against the current testdata of a problem without creating a record
+pub async fn judge_file(
+    conn: &DatabaseConnection,
+    problem_id: Uuid,
+    language: String,
+    code: String,
+) -> Result<(Status, Tests)> {
+    if language == "output" {
+        anyhow::bail!("outputs can only be judged through a submission");
+    }
+    let model = problem::Entity::find_by_id(problem_id.to_string())
+        .one(conn)
+        .await?
+        .with_context(|| format!("no problem has the id `{}`", problem_id))?;
+    let testdata = if model.testdata.is_empty() {
+        commit(conn, model.clone()).await?
+    } else {
+        model.testdata.clone()
+    };
+    let j = judge_of(Uuid::new_v4(), testdata, model, language, code);
+    tokio::task::spawn_blocking(move || judge(j)).await?
+}
+
+// judges a source against a problem directory, which needs neither the server nor the database
+pub async fn judge_local(dir: &Path, language: String, code: String) -> Result<(Status, Tests)> {
+    let manifest = manifest(dir)?;
+    if manifest.kind == Kind::OutputOnly {
+        anyhow::bail!("output only problems cannot be judged locally");
+    }
+    let testdata = match dir.join("testdata") {
+        x if x.is_dir() => x,
+        _ => dir.to_path_buf(),
+    };
+    let j = Judge {
+        record_id: Uuid::new_v4(),
+        problem_id: Uuid::nil(),
+        testdata: String::new(),
+        kind: manifest.kind,
+        time_limit: manifest.limit_time,
+        memory_limit: manifest.limit_memory,
+        input_file: manifest.input_file,
+        output_file: manifest.output_file,
+        language,
+        code,
+    };
+    tokio::task::spawn_blocking(move || judge_dir(&testdata.to_string_lossy(), j)).await?
+}
+
+// the verdict a source claims for itself, as a comment starting with `expect: wrong_answer`
+pub fn expected_of(code: &str) -> Result<Option<Status>> {
+    for line in code.lines() {
+        let line = line.trim_start();
+        let comment = ["//", "/*", "*"].iter().find_map(|x| line.strip_prefix(x));
+        if let Some(status) = comment.and_then(|x| x.trim_start().strip_prefix("expect:")) {
+            let status = status.trim().trim_end_matches("*/").trim();
+            return Ok(Some(Status::from_str(status)?));
+        }
+    }
+    Ok(None)
+}
+
+pub async fn config_check() -> BTreeMap<String, Result<(), String>> {
+    match crate::connect().await {
+        Ok(conn) => health::checks(&conn).await,
+        Err(e) => {
+            let mut checks = BTreeMap::new();
+            checks.insert("database".to_string(), Err(format!("{:#}", e)));
+            checks
+        }
+    }
+}
+
+pub fn language_of(path: &str) -> Option<&'static str> {
+    match path.rsplit('.').next()? {
+        "c" => Some("c"),
+        "cpp" | "cc" | "cxx" | "c++" => Some("cxx"),
+        _ => None,
+    }
+}
+
+pub fn print_tests(tests: &Tests) {
+    match tests {
+        Tests::Message(message) => println!("{}", message),
+        Tests::Tests(tests) => {
+            println!("{:<24} {:<24} {:>8} {:>10}", "test", "status", "ms", "KiB");
+            for test in tests {
+                println!(
+                    "{:<24} {:<24} {:>8} {:>10}",
+                    test.name,
+                    Into::<&'static str>::into(test.status),
+                    test.time,
+                    test.memory / 1024
+                );
+                if !test.message.is_empty() {
+                    println!("  {}", test.message);
+                }
+            }
+        }
+    }
+}
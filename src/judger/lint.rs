@@ -0,0 +1,155 @@
+use super::run::{run, RunConfig, RunStatistics, RunStatus};
+use super::{compile, testdata_dir};
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::os::unix::fs::PermissionsExt;
+use uuid::Uuid;
+
+pub const HELPERS: [&str; 4] = [
+    "checker.cpp",
+    "interactor.cpp",
+    "validator.cpp",
+    "testlib.h",
+];
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Lint {
+    pub name: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Health {
+    pub tests: Vec<String>,
+    pub lints: Vec<Lint>,
+}
+
+pub fn is_testdata_name(name: &str) -> bool {
+    let plain = !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\']);
+    plain && (name.ends_with(".in") || name.ends_with(".out") || HELPERS.contains(&name))
+}
+
+pub fn normalize(data: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(data.len());
+    let mut iter = data.iter().peekable();
+    while let Some(&c) = iter.next() {
+        if c == b'\r' && iter.peek() == Some(&&b'\n') {
+            continue;
+        }
+        result.push(c);
+    }
+    result
+}
+
+pub fn lint_text(data: &[u8]) -> Vec<&'static str> {
+    let mut messages = vec![];
+    if std::str::from_utf8(data).is_err() {
+        messages.push("not valid UTF-8");
+    } else if !data.is_ascii() {
+        messages.push("contains non-ASCII characters");
+    }
+    if data.windows(2).any(|x| x == b"\r\n") {
+        messages.push("contains CRLF line endings");
+    }
+    if !data.is_empty() && !data.ends_with(b"\n") {
+        messages.push("missing trailing newline");
+    }
+    messages
+}
+
+// inputs and answers missing their counterpart, which the judger would silently skip
+pub fn unpaired(names: &BTreeSet<String>) -> Vec<Lint> {
+    let mut lints = vec![];
+    for name in names.iter() {
+        let message = if let Some(stem) = name.strip_suffix(".in") {
+            Some("input without answer").filter(|_| !names.contains(&format!("{}.out", stem)))
+        } else if let Some(stem) = name.strip_suffix(".out") {
+            Some("answer without input").filter(|_| !names.contains(&format!("{}.in", stem)))
+        } else {
+            None
+        };
+        if let Some(message) = message {
+            lints.push(Lint {
+                name: name.to_string(),
+                message: message.to_string(),
+            });
+        }
+    }
+    lints
+}
+
+pub fn lint(problem_id: Uuid) -> Result<Health> {
+    let dir = testdata_dir(problem_id);
+    let names = match std::fs::read_dir(&dir) {
+        Err(_) => BTreeSet::new(),
+        Ok(x) => x
+            .filter_map(|x| x.ok())
+            .filter_map(|x| x.file_name().into_string().ok())
+            .collect::<BTreeSet<_>>(),
+    };
+    let mut lints = vec![];
+    let mut lint = |name: &str, message: &str| {
+        lints.push(Lint {
+            name: name.to_string(),
+            message: message.to_string(),
+        })
+    };
+    let mut tests = vec![];
+    for x in unpaired(&names) {
+        lint(&x.name, &x.message);
+    }
+    for name in names.iter() {
+        if !is_testdata_name(name) {
+            lint(name, "unknown file");
+        } else if let Some(stem) = name.strip_suffix(".in") {
+            if names.contains(&format!("{}.out", stem)) {
+                tests.push(stem.to_string());
+            }
+        }
+        if name.ends_with(".in") || name.ends_with(".out") {
+            for message in lint_text(&std::fs::read(format!("{}/{}", dir, name))?) {
+                lint(name, message);
+            }
+        }
+    }
+    if names.contains("validator.cpp") {
+        let workspace = format!("/tmp/boardjudge/lint/{}", Uuid::new_v4());
+        std::fs::create_dir_all(&workspace)?;
+        std::fs::set_permissions(&workspace, std::fs::Permissions::from_mode(0o777))?;
+        let validator = format!("{}/validator", workspace);
+        if compile("cxx", &format!("{}/validator.cpp", dir), &validator)? {
+            for name in names.iter().filter(|x| x.ends_with(".in")) {
+                let log = format!("{}/validator.log", workspace);
+                let result = run(&RunConfig {
+                    time_limit: 10_000,
+                    memory_limit: 256 << 20,
+                    exec_path: &validator,
+                    input_path: &format!("{}/{}", dir, name),
+                    output_path: "/dev/null",
+                    error_path: &log,
+                    seccomp_rule: Some("general"),
+                    work_dir: None,
+                    env: &[],
+                    args: &["validator".as_bytes()],
+                });
+                match result {
+                    Ok(RunStatistics {
+                        status: RunStatus::Success,
+                        code: 0,
+                        ..
+                    }) => (),
+                    _ => {
+                        let message = std::fs::read_to_string(&log).unwrap_or_default();
+                        lint(name, &format!("rejected by validator: {}", message.trim()));
+                    }
+                }
+            }
+        } else {
+            lint("validator.cpp", "compilation failed");
+        }
+        std::fs::remove_dir_all(&workspace)?;
+    }
+    Ok(Health { tests, lints })
+}
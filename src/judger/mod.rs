@@ -1,19 +1,36 @@
+pub mod lint;
 pub mod run;
+pub mod version;
+
+#[cfg(test)]
+mod tests;
 
 use self::run::RunStatistics;
-use self::run::{run, RunConfig, RunError, RunStatus};
+use self::run::{interact, run, RunConfig, RunError, RunStatus};
+use self::version::version_dir;
 use crate::config;
+use crate::metrics::metrics;
 use anyhow::Result;
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
+use std::future::Future;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc::unbounded_channel as mpsc_channel;
 use tokio::sync::mpsc::UnboundedSender as MpscSender;
 use tokio::sync::oneshot::channel as oneshot_channel;
 use tokio::sync::oneshot::Sender as OneshotSender;
+use tokio::sync::{Mutex, RwLock};
 use uuid::Uuid;
 use Status::*;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub enum Status {
     JudgeFailed,
     TestdataError,
@@ -42,22 +59,36 @@ impl From<Status> for &'static str {
     }
 }
 
-impl Serialize for Status {
-    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        serializer.serialize_str(Into::<&'static str>::into(*self))
+impl FromStr for Status {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "judge_failed" => Ok(JudgeFailed),
+            "testdata_error" => Ok(TestdataError),
+            "compilation_error" => Ok(CompilationError),
+            "runtime_error" => Ok(RuntimeError),
+            "time_limit_exceeded" => Ok(TimeLimitExceeded),
+            "memory_limit_exceeded" => Ok(MemoryLimitExceeded),
+            "wrong_answer" => Ok(WrongAnswer),
+            "accepted" => Ok(Accepted),
+            "skipped" => Ok(Skipped),
+            _ => Err(anyhow::anyhow!("unknown status `{}`", s)),
+        }
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct Test {
     pub name: String,
     pub status: Status,
     pub time: u32,
     pub memory: u32,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub message: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
-#[serde(tag = "type")]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(tag = "type", content = "content")]
 pub enum Tests {
     Message(String),
     Tests(Vec<Test>),
@@ -69,65 +100,140 @@ impl From<&str> for Tests {
     }
 }
 
-pub fn judge(
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    #[default]
+    Traditional,
+    Interactive,
+    OutputOnly,
+}
+
+impl From<Kind> for &'static str {
+    fn from(k: Kind) -> Self {
+        match k {
+            Kind::Traditional => "traditional",
+            Kind::Interactive => "interactive",
+            Kind::OutputOnly => "output_only",
+        }
+    }
+}
+
+impl FromStr for Kind {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "traditional" => Ok(Kind::Traditional),
+            "interactive" => Ok(Kind::Interactive),
+            "output_only" => Ok(Kind::OutputOnly),
+            _ => Err(anyhow::anyhow!("unknown problem kind `{}`", s)),
+        }
+    }
+}
+
+// every judge works in its own directory below, so judgers in several processes can share a host
+const WORKSPACE: &str = "/tmp/boardjudge/judge";
+
+pub fn testdata_dir(problem_id: Uuid) -> String {
+    format!("{}/{}/{}", config().judger.root, "testdata", problem_id)
+}
+
+pub fn output_dir(record_id: Uuid) -> String {
+    format!("{}/{}/{}", config().judger.root, "output", record_id)
+}
+
+pub fn judge(j: Judge) -> Result<(Status, Tests)> {
+    let dir = match j.testdata.as_str() {
+        "" => testdata_dir(j.problem_id),
+        x => version_dir(j.problem_id, x),
+    };
+    judge_dir(&dir, j)
+}
+
+// judges against the testdata in `dir`, which does not have to belong to a problem
+pub fn judge_dir(dir: &str, j: Judge) -> Result<(Status, Tests)> {
+    let workspace = format!("{}/{}", WORKSPACE, Uuid::new_v4());
+    let result = judge_in(&workspace, dir, j);
+    let _ = std::fs::remove_dir_all(&workspace);
+    result
+}
+
+fn judge_in(
+    workspace: &str,
+    dir: &str,
     Judge {
-        record_id: _,
-        problem_id,
+        record_id,
+        problem_id: _,
+        testdata: _,
+        kind,
         time_limit,
         memory_limit,
+        input_file,
+        output_file,
         language,
         code,
     }: Judge,
 ) -> Result<(Status, Tests)> {
-    let _ = std::fs::remove_dir_all("/tmp/boardjudge/judge");
-    let testdata =
-        match std::fs::read_dir(format!("{}/{}/testdata", config().judger.root, problem_id)) {
-            Err(_) => return Ok((TestdataError, "{}".into())),
-            Ok(x) => x,
+    let testdata = match std::fs::read_dir(dir) {
+        Err(_) => return Ok((TestdataError, "{}".into())),
+        Ok(x) => x,
+    }
+    .filter_map(|x| x.ok())
+    .filter_map(|x| x.file_name().into_string().ok())
+    .collect::<Vec<_>>();
+    std::fs::create_dir_all(workspace)?;
+    std::fs::set_permissions(workspace, std::fs::Permissions::from_mode(0o755))?;
+    // compilers run as nobody, so they write into `build` and the results are copied out of reach
+    let build = format!("{}/build", workspace);
+    std::fs::create_dir_all(&build)?;
+    std::fs::set_permissions(&build, std::fs::Permissions::from_mode(0o777))?;
+    std::fs::write(format!("{}/code", build), code)?;
+    if kind != Kind::OutputOnly
+        && !compile(
+            &language,
+            &format!("{}/code", build),
+            &format!("{}/a.out", build),
+        )?
+    {
+        return Ok((CompilationError, "{}".into()));
+    }
+    let checker = if testdata.iter().any(|x| x == "checker.cpp") {
+        if !compile(
+            "cxx",
+            &format!("{}/checker.cpp", dir),
+            &format!("{}/checker", build),
+        )? {
+            return Ok((TestdataError, "checker compilation failed".into()));
         }
-        .into_iter()
-        .filter_map(|x| x.ok())
-        .filter_map(|x| x.file_name().into_string().ok())
-        .collect::<Vec<_>>();
-    std::fs::create_dir_all("/tmp/boardjudge/judge/code")?;
-    std::fs::write("/tmp/boardjudge/judge/code", code)?;
-    match run(&RunConfig {
-        time_limit: 10_000,
-        memory_limit: 256 << 20,
-        exec_path: match language.as_str() {
-            "c" => &config().judger.compiler_c,
-            "cxx" => &config().judger.compiler_cxx,
-            _ => return Ok((CompilationError, "{}".into())),
-        },
-        input_path: "/dev/null",
-        output_path: "/dev/null",
-        env: &[],
-        args: &[
-            "/tmp/boardjudge/judge/code".as_bytes(),
-            "-o".as_bytes(),
-            "/tmp/boardjudge/judge/a.out".as_bytes(),
-            "-g".as_bytes(),
-            "-Wall".as_bytes(),
-            "-static-libgcc".as_bytes(),
-            "-fexec-charset=UTF-8".as_bytes(),
-            "-std=c++2b".as_bytes(),
-            "-march=native".as_bytes(),
-        ],
-    }) {
-        Ok(t) => {
-            if t.status != RunStatus::Success || t.code != 0 {
-                return Ok((CompilationError, "{}".into()));
-            }
+        Some(format!("{}/checker", workspace))
+    } else {
+        None
+    };
+    if kind == Kind::Interactive
+        && !compile(
+            "cxx",
+            &format!("{}/interactor.cpp", dir),
+            &format!("{}/interactor", build),
+        )?
+    {
+        return Ok((TestdataError, "interactor compilation failed".into()));
+    }
+    for name in ["a.out", "checker", "interactor"] {
+        let from = format!("{}/{}", build, name);
+        if Path::new(&from).exists() {
+            let to = format!("{}/{}", workspace, name);
+            std::fs::copy(&from, &to)?;
+            std::fs::set_permissions(&to, std::fs::Permissions::from_mode(0o755))?;
         }
-        Err(RunError::Internal) => return Ok((CompilationError, "{}".into())),
     }
+    std::fs::remove_dir_all(&build)?;
     let mut inputs = BTreeSet::new();
     let mut outputs = BTreeSet::new();
     for name in testdata {
-        if name.ends_with(".in") {
-            inputs.insert(name);
-        } else if name.ends_with(".out") {
-            outputs.insert(name);
+        if let Some(name) = name.strip_suffix(".in") {
+            inputs.insert(name.to_string());
+        } else if let Some(name) = name.strip_suffix(".out") {
+            outputs.insert(name.to_string());
         }
     }
     let mut xests = inputs
@@ -138,119 +244,310 @@ pub fn judge(
             status: Skipped,
             time: 0,
             memory: 0,
+            message: String::new(),
         })
         .collect::<Vec<Test>>();
     let mut xtatus = Accepted;
     for test in xests.iter_mut() {
-        match run(&RunConfig {
+        let input = format!("{}/{}.in", dir, test.name);
+        let answer = format!("{}/{}.out", dir, test.name);
+        let output = format!("{}/output", workspace);
+        let file_io = kind == Kind::Traditional && (input_file.is_some() || output_file.is_some());
+        // a writable directory holding only the input, made afresh for every test
+        let sandbox = format!("{}/run", workspace);
+        if file_io {
+            let _ = std::fs::remove_dir_all(&sandbox);
+            std::fs::create_dir_all(&sandbox)?;
+            std::fs::set_permissions(&sandbox, std::fs::Permissions::from_mode(0o777))?;
+        }
+        if let Some(input_file) = input_file.as_ref().filter(|_| file_io) {
+            std::fs::copy(&input, format!("{}/{}", sandbox, input_file))?;
+        }
+        let solution = RunConfig {
             time_limit,
             memory_limit: memory_limit as u64,
-            exec_path: "/tmp/boardjudge/judge/a.out",
-            input_path: format!(
-                "{}/{}/testdata/{}.in",
-                config().judger.root,
-                problem_id,
-                test.name
-            )
-            .as_str(),
-            output_path: "/tmp/boardjudge/judge/output",
+            exec_path: &format!("{}/a.out", workspace),
+            input_path: match input_file {
+                Some(_) if file_io => "/dev/null",
+                _ => &input,
+            },
+            output_path: match output_file {
+                Some(_) if file_io => "/dev/null",
+                _ => &output,
+            },
+            error_path: "/dev/null",
+            seccomp_rule: Some(if file_io { "c_cpp_file_io" } else { "c_cpp" }),
+            work_dir: if file_io { Some(&sandbox) } else { None },
             env: &[],
             args: &[],
-        }) {
-            Ok(RunStatistics {
-                time,
-                memory,
-                code: _,
-                status: RunStatus::Success,
-            }) => {
-                let answer = std::fs::read(format!(
-                    "{}/{}/testdata/{}.in",
-                    config().judger.root,
-                    problem_id,
-                    test.name
-                ))?;
-                let output = std::fs::read("/tmp/boardjudge/judge/a.out")?;
-                *test = Test {
-                    name: std::mem::replace(&mut test.name, "".to_string()),
-                    status: if ojcmp::Comparison::AC
-                        == ojcmp::try_normal_compare(
-                            &mut answer.as_slice(),
-                            &mut output.as_slice(),
-                        )? {
-                        Accepted
-                    } else {
-                        WrongAnswer
+        };
+        let (status, time, memory, message) = match kind {
+            Kind::Traditional => match run(&solution) {
+                Ok(
+                    x @ RunStatistics {
+                        status: RunStatus::Success,
+                        ..
                     },
-                    time,
-                    memory: memory as u32,
-                };
-            }
-            Ok(RunStatistics {
-                time,
-                memory,
-                code: _,
-                status: RunStatus::RuntimeError,
-            }) => {
-                *test = Test {
-                    name: std::mem::replace(&mut test.name, "".to_string()),
-                    status: RuntimeError,
-                    time,
-                    memory: memory as u32,
-                };
-                xtatus = RuntimeError;
-                break;
-            }
-            Ok(RunStatistics {
-                time,
-                memory,
-                code: _,
-                status: RunStatus::TimeLimitExceeded,
-            }) => {
-                *test = Test {
-                    name: std::mem::replace(&mut test.name, "".to_string()),
-                    status: MemoryLimitExceeded,
-                    time,
-                    memory: memory as u32,
-                };
-                xtatus = RuntimeError;
-                break;
-            }
-            Ok(RunStatistics {
-                time,
-                memory,
-                code: _,
-                status: RunStatus::MemoryLimitExceeded,
-            }) => {
-                *test = Test {
-                    name: std::mem::replace(&mut test.name, "".to_string()),
-                    status: MemoryLimitExceeded,
-                    time,
-                    memory: memory as u32,
+                ) => {
+                    let collected = match output_file.as_ref().filter(|_| file_io) {
+                        // a symlink would let the solution read files of the judger
+                        Some(output_file) => {
+                            let path = format!("{}/{}", sandbox, output_file);
+                            std::fs::symlink_metadata(&path)
+                                .map(|x| x.is_file())
+                                .unwrap_or(false)
+                                && std::fs::copy(&path, &output).is_ok()
+                        }
+                        None => true,
+                    };
+                    if collected {
+                        let (status, message) =
+                            check(workspace, &input, &output, &answer, checker.as_deref())?;
+                        (status, x.time, x.memory, message)
+                    } else {
+                        let message = "output file not found".to_string();
+                        (WrongAnswer, x.time, x.memory, message)
+                    }
+                }
+                Ok(x) => (verdict(x.status), x.time, x.memory, String::new()),
+                Err(RunError::Internal) => {
+                    metrics().sandbox_errors.inc();
+                    (JudgeFailed, 0, 0, String::new())
+                }
+            },
+            Kind::Interactive => {
+                let log = format!("{}/interactor.log", workspace);
+                let interactor = RunConfig {
+                    time_limit: time_limit.saturating_mul(2),
+                    memory_limit: 256 << 20,
+                    exec_path: &format!("{}/interactor", workspace),
+                    input_path: "/dev/null",
+                    output_path: "/dev/null",
+                    error_path: &log,
+                    // trusted, a testlib interactor opens its result file for writing
+                    seccomp_rule: None,
+                    work_dir: None,
+                    env: &[],
+                    args: &[
+                        "interactor".as_bytes(),
+                        input.as_bytes(),
+                        "/dev/null".as_bytes(),
+                        answer.as_bytes(),
+                    ],
                 };
-                xtatus = RuntimeError;
-                break;
+                match interact(&solution, &interactor, workspace) {
+                    Ok((x, y)) => {
+                        let message = std::fs::read_to_string(&log).unwrap_or_default();
+                        let status = match (x.status, y.status) {
+                            (_, RunStatus::Success) if testlib(y.code) == JudgeFailed => {
+                                JudgeFailed
+                            }
+                            (RunStatus::Success, RunStatus::Success) => testlib(y.code),
+                            (RunStatus::Success, _) => JudgeFailed,
+                            (status, _) => verdict(status),
+                        };
+                        (status, x.time, x.memory, message)
+                    }
+                    Err(RunError::Internal) => {
+                        metrics().sandbox_errors.inc();
+                        (JudgeFailed, 0, 0, String::new())
+                    }
+                }
             }
-            Err(RunError::Internal) => {
-                *test = Test {
-                    name: std::mem::replace(&mut test.name, "".to_string()),
-                    status: JudgeFailed,
-                    time: 0,
-                    memory: 0,
-                };
-                xtatus = RuntimeError;
-                break;
+            Kind::OutputOnly => {
+                match std::fs::copy(
+                    format!("{}/{}.out", output_dir(record_id), test.name),
+                    &output,
+                ) {
+                    Ok(_) => {
+                        let (status, message) =
+                            check(workspace, &input, &output, &answer, checker.as_deref())?;
+                        (status, 0, 0, message)
+                    }
+                    Err(_) => (WrongAnswer, 0, 0, "output file not found".to_string()),
+                }
             }
+        };
+        *test = Test {
+            name: std::mem::take(&mut test.name),
+            status,
+            time,
+            memory: memory as u32,
+            message,
+        };
+        if !matches!(status, Accepted) {
+            xtatus = status;
+            break;
         }
     }
-    std::fs::remove_dir_all("/tmp/boardjudge/judge")?;
     Ok((xtatus, Tests::Tests(xests)))
 }
 
+fn verdict(status: RunStatus) -> Status {
+    match status {
+        RunStatus::Success => Accepted,
+        RunStatus::TimeLimitExceeded => TimeLimitExceeded,
+        RunStatus::MemoryLimitExceeded => MemoryLimitExceeded,
+        RunStatus::RuntimeError => RuntimeError,
+    }
+}
+
+fn testlib(code: u32) -> Status {
+    match code {
+        0 => Accepted,
+        1 | 2 => WrongAnswer,
+        _ => JudgeFailed,
+    }
+}
+
+fn check(
+    workspace: &str,
+    input: &str,
+    output: &str,
+    answer: &str,
+    checker: Option<&str>,
+) -> Result<(Status, String)> {
+    let checker = match checker {
+        Some(x) => x,
+        None => {
+            let answer = std::fs::read(answer)?;
+            let output = std::fs::read(output)?;
+            let status = if ojcmp::Comparison::AC
+                == ojcmp::try_normal_compare(&mut answer.as_slice(), &mut output.as_slice())?
+            {
+                Accepted
+            } else {
+                WrongAnswer
+            };
+            return Ok((status, String::new()));
+        }
+    };
+    let log = format!("{}/checker.log", workspace);
+    let result = run(&RunConfig {
+        time_limit: 10_000,
+        memory_limit: 256 << 20,
+        exec_path: checker,
+        input_path: "/dev/null",
+        output_path: "/dev/null",
+        error_path: &log,
+        seccomp_rule: None,
+        work_dir: None,
+        env: &[],
+        args: &[
+            "checker".as_bytes(),
+            input.as_bytes(),
+            output.as_bytes(),
+            answer.as_bytes(),
+        ],
+    });
+    let message = std::fs::read_to_string(&log).unwrap_or_default();
+    match result {
+        Ok(RunStatistics {
+            status: RunStatus::Success,
+            code,
+            ..
+        }) => Ok((testlib(code), message)),
+        _ => Ok((JudgeFailed, message)),
+    }
+}
+
+const SELF_TEST: &str = "/tmp/boardjudge/self_test";
+
+// a sandboxed hello world needs the compilers, libjudger, seccomp and root privileges
+pub fn self_test() -> Result<()> {
+    let _ = std::fs::remove_dir_all(SELF_TEST);
+    std::fs::create_dir_all(SELF_TEST)?;
+    std::fs::set_permissions(SELF_TEST, std::fs::Permissions::from_mode(0o777))?;
+    let source = format!("{}/code", SELF_TEST);
+    let target = format!("{}/a.out", SELF_TEST);
+    let output = format!("{}/output", SELF_TEST);
+    std::fs::write(
+        &source,
+        "#include <stdio.h>\nint main(void) { puts(\"hello world\"); return 0; }\n",
+    )?;
+    if !compile("c", &source, &target)? {
+        return Err(anyhow::anyhow!("hello world failed to compile"));
+    }
+    let x = run(&RunConfig {
+        time_limit: 1_000,
+        memory_limit: 256 << 20,
+        exec_path: &target,
+        input_path: "/dev/null",
+        output_path: &output,
+        error_path: "/dev/null",
+        seccomp_rule: Some("c_cpp"),
+        work_dir: None,
+        env: &[],
+        args: &[],
+    })
+    .map_err(|_| anyhow::anyhow!("the sandbox failed to run hello world"))?;
+    if x.status != RunStatus::Success || x.code != 0 {
+        return Err(anyhow::anyhow!("hello world exited with {:?}", x.status));
+    }
+    if std::fs::read_to_string(&output)?.trim_end() != "hello world" {
+        return Err(anyhow::anyhow!("hello world printed a wrong answer"));
+    }
+    Ok(())
+}
+
+fn compile(language: &str, source: &str, target: &str) -> Result<bool> {
+    let (exec_path, lang, std) = match language {
+        "c" => (&config().judger.compiler_c, "c", "-std=c2x"),
+        "cxx" => (&config().judger.compiler_cxx, "c++", "-std=c++2b"),
+        _ => return Ok(false),
+    };
+    let include = source.rsplit_once('/').map(|x| x.0).unwrap_or(".");
+    match run(&RunConfig {
+        time_limit: 10_000,
+        memory_limit: 256 << 20,
+        exec_path,
+        input_path: "/dev/null",
+        output_path: "/dev/null",
+        error_path: "/dev/null",
+        seccomp_rule: None,
+        work_dir: None,
+        env: &["PATH=/usr/bin:/bin".as_bytes()],
+        args: &[
+            exec_path.as_bytes(),
+            "-x".as_bytes(),
+            lang.as_bytes(),
+            source.as_bytes(),
+            "-o".as_bytes(),
+            target.as_bytes(),
+            "-I".as_bytes(),
+            include.as_bytes(),
+            "-g".as_bytes(),
+            "-Wall".as_bytes(),
+            "-static-libgcc".as_bytes(),
+            "-fexec-charset=UTF-8".as_bytes(),
+            std.as_bytes(),
+            "-march=native".as_bytes(),
+        ],
+    }) {
+        Ok(t) if t.status == RunStatus::Success && t.code == 0 => Ok(true),
+        Ok(_) => {
+            metrics()
+                .compile_failures
+                .with_label_values(&[language])
+                .inc();
+            Ok(false)
+        }
+        Err(RunError::Internal) => {
+            metrics().sandbox_errors.inc();
+            Ok(false)
+        }
+    }
+}
+
 pub struct Judge {
     pub record_id: Uuid,
     pub problem_id: Uuid,
+    pub testdata: String,
+    pub kind: Kind,
     pub time_limit: u32,
     pub memory_limit: u32,
+    pub input_file: Option<String>,
+    pub output_file: Option<String>,
     pub language: String,
     pub code: String,
 }
@@ -258,23 +555,79 @@ pub struct Judge {
 #[derive(Clone)]
 pub struct Judger {
     sender: MpscSender<(Judge, OneshotSender<(Status, Tests)>)>,
+    draining: Arc<AtomicBool>,
+    busy: Arc<Mutex<()>>,
+    tasks: Arc<RwLock<()>>,
 }
 
 impl Judger {
     pub fn daemon() -> Judger {
         let (tx, mut rx) = mpsc_channel::<(Judge, OneshotSender<(Status, Tests)>)>();
-        tokio::spawn(async move {
-            while let Some((j, s)) = rx.recv().await {
-                let (status, result) = tokio::task::block_in_place(move || judge(j))
-                    .unwrap_or((JudgeFailed, "{}".into()));
-                let _ = s.send((status, result));
+        let draining = Arc::new(AtomicBool::new(false));
+        let busy = Arc::new(Mutex::new(()));
+        tokio::spawn({
+            let draining = draining.clone();
+            let busy = busy.clone();
+            async move {
+                while let Some((j, s)) = rx.recv().await {
+                    metrics().judge_queue.dec();
+                    let _guard = busy.lock().await;
+                    // queued judges are dropped unanswered and stay `waiting` until the next boot
+                    if draining.load(Ordering::SeqCst) {
+                        continue;
+                    }
+                    let language = j.language.clone();
+                    let start = Instant::now();
+                    let (status, result) = tokio::task::block_in_place(move || judge(j))
+                        .unwrap_or((JudgeFailed, "{}".into()));
+                    metrics()
+                        .judge_duration
+                        .with_label_values(&[&language])
+                        .observe(start.elapsed().as_secs_f64());
+                    metrics().verdicts.with_label_values(&[status.into()]).inc();
+                    let _ = s.send((status, result));
+                }
             }
         });
-        Judger { sender: tx }
+        Judger {
+            sender: tx,
+            draining,
+            busy,
+            tasks: Arc::new(RwLock::new(())),
+        }
     }
-    pub async fn judge(&self, j: Judge) -> (Status, Tests) {
+    pub async fn judge(&self, j: Judge) -> Option<(Status, Tests)> {
         let (tx, rx) = oneshot_channel();
-        let _ = self.sender.send((j, tx));
-        rx.await.unwrap_or((JudgeFailed, "{}".into()))
+        metrics().judge_queue.inc();
+        if self.sender.send((j, tx)).is_err() {
+            metrics().judge_queue.dec();
+        }
+        rx.await.ok()
+    }
+    pub fn draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+    pub fn stop(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+    // runs a task which `drain` waits for, such as one saving a verdict
+    pub fn spawn<F: Future<Output = ()> + Send + 'static>(&self, f: F) {
+        // a draining judger holds the write lock and takes no more tasks
+        if let Ok(guard) = self.tasks.clone().try_read_owned() {
+            tokio::spawn(async move {
+                f.await;
+                drop(guard);
+            });
+        }
+    }
+    // stops taking judges from the queue and waits for the running one and every task
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        self.stop();
+        tokio::time::timeout(timeout, async {
+            let _busy = self.busy.lock().await;
+            let _tasks = self.tasks.write().await;
+        })
+        .await
+        .is_ok()
     }
 }
@@ -1,7 +1,10 @@
 use crate::config;
 use crate::sys::libjudger;
 use std::ffi::CString;
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::PermissionsExt;
 use std::ptr::null_mut;
+use std::time::Duration;
 
 #[derive(Debug, Copy, Clone)]
 pub struct RunConfig<'a> {
@@ -10,6 +13,9 @@ pub struct RunConfig<'a> {
     pub exec_path: &'a str,
     pub input_path: &'a str,
     pub output_path: &'a str,
+    pub error_path: &'a str,
+    pub seccomp_rule: Option<&'a str>,
+    pub work_dir: Option<&'a str>,
     pub env: &'a [&'a [u8]],
     pub args: &'a [&'a [u8]],
 }
@@ -36,14 +42,35 @@ pub enum RunError {
 }
 
 pub fn run(c: &RunConfig) -> Result<RunStatistics, RunError> {
+    if let Some(work_dir) = c.work_dir {
+        // after unshare the working directory belongs to this thread only
+        return std::thread::scope(|s| {
+            s.spawn(|| {
+                if unsafe { libc::unshare(libc::CLONE_FS) } != 0 {
+                    return Err(RunError::Internal);
+                }
+                std::env::set_current_dir(work_dir).map_err(|_| RunError::Internal)?;
+                run(&RunConfig {
+                    work_dir: None,
+                    ..*c
+                })
+            })
+            .join()
+            .map_err(|_| RunError::Internal)?
+        });
+    }
     let mut arg2;
     unsafe {
         const CONST_NONE_CSTRING: Option<CString> = None;
         let ffi_exe_path = CString::new(c.exec_path).map_err(|_| RunError::Internal)?;
         let ffi_input_path = CString::new(c.input_path).map_err(|_| RunError::Internal)?;
         let ffi_output_path = CString::new(c.output_path).map_err(|_| RunError::Internal)?;
+        let ffi_error_path = CString::new(c.error_path).map_err(|_| RunError::Internal)?;
         let ffi_null_path = CString::new("/dev/null").map_err(|_| RunError::Internal)?;
-        let ffi_seccomp_rule_name = CString::new("c_cpp").map_err(|_| RunError::Internal)?;
+        let ffi_seccomp_rule_name = match c.seccomp_rule {
+            Some(x) => Some(CString::new(x).map_err(|_| RunError::Internal)?),
+            None => None,
+        };
         let mut ffi_env = [CONST_NONE_CSTRING; 256];
         let mut ffi_args = [CONST_NONE_CSTRING; 256];
         for (i, &j) in c.env.iter().enumerate().take(256) {
@@ -63,11 +90,14 @@ pub fn run(c: &RunConfig) -> Result<RunStatistics, RunError> {
             exe_path: ffi_exe_path.as_ptr() as *mut i8,
             input_path: ffi_input_path.as_ptr() as *mut i8,
             output_path: ffi_output_path.as_ptr() as *mut i8,
-            error_path: ffi_null_path.as_ptr() as *mut i8,
+            error_path: ffi_error_path.as_ptr() as *mut i8,
             args: ffi_args.map(|x| x.map(|y| y.as_ptr() as *mut i8).unwrap_or(null_mut())),
             env: ffi_env.map(|x| x.map(|y| y.as_ptr() as *mut i8).unwrap_or(null_mut())),
             log_path: ffi_null_path.as_ptr() as *mut i8,
-            seccomp_rule_name: ffi_seccomp_rule_name.as_ptr() as *mut i8,
+            seccomp_rule_name: ffi_seccomp_rule_name
+                .as_ref()
+                .map(|x| x.as_ptr() as *mut i8)
+                .unwrap_or(null_mut()),
             uid: 65534,
             gid: 65534,
         };
@@ -98,3 +128,72 @@ pub fn run(c: &RunConfig) -> Result<RunStatistics, RunError> {
         },
     })
 }
+
+pub fn interact(
+    solution: &RunConfig,
+    interactor: &RunConfig,
+    fifo_dir: &str,
+) -> Result<(RunStatistics, RunStatistics), RunError> {
+    // solution -> b -> relay -> c -> interactor -> d -> relay -> a -> solution
+    let a = format!("{}/a.fifo", fifo_dir);
+    let b = format!("{}/b.fifo", fifo_dir);
+    let c = format!("{}/c.fifo", fifo_dir);
+    let d = format!("{}/d.fifo", fifo_dir);
+    // every test makes its fifos afresh, so they are removed however it ends
+    let result = [&a, &b, &c, &d]
+        .into_iter()
+        .try_for_each(|x| mkfifo(x))
+        .and_then(|_| relayed(solution, interactor, [&a, &b, &c, &d]));
+    for path in [&a, &b, &c, &d] {
+        let _ = std::fs::remove_file(path);
+    }
+    result
+}
+
+fn relayed(
+    solution: &RunConfig,
+    interactor: &RunConfig,
+    [a, b, c, d]: [&String; 4],
+) -> Result<(RunStatistics, RunStatistics), RunError> {
+    let solution = RunConfig {
+        input_path: a,
+        output_path: b,
+        ..*solution
+    };
+    let interactor = RunConfig {
+        input_path: c,
+        output_path: d,
+        ..*interactor
+    };
+    std::thread::scope(|s| {
+        let relays = [s.spawn(|| relay(b, c)), s.spawn(|| relay(d, a))];
+        let x = s.spawn(|| run(&solution));
+        let y = s.spawn(|| run(&interactor));
+        let x = x.join().map_err(|_| RunError::Internal);
+        let y = y.join().map_err(|_| RunError::Internal);
+        // a sandboxed process which died before opening its fifos leaves a relay blocked in open
+        while relays.iter().any(|r| !r.is_finished()) {
+            for path in [a, b, c, d] {
+                let _ = OpenOptions::new().read(true).write(true).open(path);
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        Ok((x??, y??))
+    })
+}
+
+fn mkfifo(path: &str) -> Result<(), RunError> {
+    let ffi_path = CString::new(path).map_err(|_| RunError::Internal)?;
+    if unsafe { libc::mkfifo(ffi_path.as_ptr(), 0o666) } != 0 {
+        return Err(RunError::Internal);
+    }
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o666))
+        .map_err(|_| RunError::Internal)
+}
+
+fn relay(from: &str, to: &str) -> std::io::Result<u64> {
+    // the writing end is opened first, otherwise both sides block on their stdin
+    let mut to = OpenOptions::new().write(true).open(to)?;
+    let mut from = File::open(from)?;
+    std::io::copy(&mut from, &mut to)
+}
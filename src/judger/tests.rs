@@ -0,0 +1,97 @@
+use super::*;
+use crate::Config;
+use std::sync::Once;
+
+fn setup() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        crate::init(toml::from_str::<Config>(include_str!("../../data/config.toml")).unwrap())
+    });
+}
+
+const INTERACTOR: &str = r#"#include <cstdio>
+int main(int argc, char **argv) {
+    FILE *input = fopen(argv[1], "r");
+    int n, x;
+    fscanf(input, "%d", &n);
+    printf("%d\n", n);
+    fflush(stdout);
+    if (scanf("%d", &x) != 1) return 1;
+    return x == n + 1 ? 0 : 1;
+}
+"#;
+
+const SOLUTION: &str = r#"#include <cstdio>
+int main() {
+    int n;
+    scanf("%d", &n);
+    printf("%d\n", n + 1);
+    return 0;
+}
+"#;
+
+// a testlib interactor opens its result file, the third argument, for writing
+const TESTLIB_INTERACTOR: &str = r#"#include "testlib.h"
+int main(int argc, char *argv[]) {
+    registerInteraction(argc, argv);
+    int n = inf.readInt();
+    std::cout << n << std::endl;
+    int x = ouf.readInt();
+    tout << x << std::endl;
+    if (x != n + 1) quitf(_wa, "expected %d, found %d", n + 1, x);
+    quitf(_ok, "n = %d", n);
+}
+"#;
+
+fn judge_interactive(files: &[(&str, &[u8])]) -> Vec<Test> {
+    setup();
+    let dir = format!("/tmp/boardjudge/test/{}", Uuid::new_v4());
+    std::fs::create_dir_all(&dir).unwrap();
+    for (name, data) in files {
+        std::fs::write(format!("{}/{}", dir, name), data).unwrap();
+    }
+    for (name, n) in [("1", 1), ("2", 41)] {
+        std::fs::write(format!("{}/{}.in", dir, name), format!("{}\n", n)).unwrap();
+        std::fs::write(format!("{}/{}.out", dir, name), "").unwrap();
+    }
+    let j = Judge {
+        record_id: Uuid::new_v4(),
+        problem_id: Uuid::nil(),
+        testdata: String::new(),
+        kind: Kind::Interactive,
+        time_limit: 1_000,
+        memory_limit: 256 << 20,
+        input_file: None,
+        output_file: None,
+        language: "cxx".to_string(),
+        code: SOLUTION.to_string(),
+    };
+    let (status, tests) = judge_dir(&dir, j).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let tests = match tests {
+        Tests::Tests(x) => x,
+        Tests::Message(x) => panic!("{}", x),
+    };
+    assert_eq!(status, Accepted, "{:?}", tests);
+    tests
+}
+
+#[test]
+#[ignore = "needs root, libjudger and clang"]
+fn interactive_tests_after_the_first() {
+    let tests = judge_interactive(&[("interactor.cpp", INTERACTOR.as_bytes())]);
+    assert_eq!(tests.len(), 2);
+    assert!(tests.iter().all(|x| x.status == Accepted));
+}
+
+#[test]
+#[ignore = "needs root, libjudger, clang and `TESTLIB` naming a testlib.h"]
+fn testlib_interactor() {
+    let testlib = std::fs::read(std::env::var("TESTLIB").unwrap()).unwrap();
+    let tests = judge_interactive(&[
+        ("interactor.cpp", TESTLIB_INTERACTOR.as_bytes()),
+        ("testlib.h", &testlib),
+    ]);
+    assert_eq!(tests.len(), 2);
+    assert!(tests.iter().all(|x| x.status == Accepted));
+}
@@ -0,0 +1,133 @@
+use super::testdata_dir;
+use crate::config;
+use crate::utils::{hex, sha256};
+use anyhow::{Context, Result};
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Version {
+    pub hash: String,
+    pub time: NaiveDateTime,
+    pub files: BTreeMap<String, String>,
+}
+
+pub fn blob_path(hash: &str) -> String {
+    format!("{}/{}/{}", config().judger.root, "blob", hash)
+}
+
+pub fn version_dir(problem_id: Uuid, hash: &str) -> String {
+    format!("{}/{}", history_dir(problem_id), hash)
+}
+
+// every version of a problem
+pub fn history_dir(problem_id: Uuid) -> String {
+    format!("{}/{}/{}", config().judger.root, "version", problem_id)
+}
+
+fn manifest_path(problem_id: Uuid, hash: &str) -> String {
+    format!("{}.json", version_dir(problem_id, hash))
+}
+
+fn is_hash(hash: &str) -> bool {
+    hash.len() == 64 && hash.bytes().all(|x| x.is_ascii_hexdigit())
+}
+
+// writes through a temporary name so that no one ever sees half a file
+fn write_atomic(path: &str, data: impl AsRef<[u8]>) -> Result<()> {
+    let temp = format!("{}.{}", path, Uuid::new_v4());
+    std::fs::write(&temp, data)?;
+    std::fs::rename(&temp, path).map_err(|e| {
+        let _ = std::fs::remove_file(&temp);
+        e.into()
+    })
+}
+
+pub fn snapshot(problem_id: Uuid) -> Result<String> {
+    std::fs::create_dir_all(format!("{}/{}", config().judger.root, "blob"))?;
+    let mut files = BTreeMap::new();
+    if let Ok(dir) = std::fs::read_dir(testdata_dir(problem_id)) {
+        for entry in dir.filter_map(|x| x.ok()) {
+            let name = match entry.file_name().into_string() {
+                Ok(x) => x,
+                Err(_) => continue,
+            };
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            let data = std::fs::read(entry.path())?;
+            let hash = hex(&sha256(&data));
+            if !Path::new(&blob_path(&hash)).exists() {
+                write_atomic(&blob_path(&hash), data)?;
+            }
+            files.insert(name, hash);
+        }
+    }
+    let mut text = String::new();
+    for (name, hash) in files.iter() {
+        text.push_str(&format!("{}\0{}\n", name, hash));
+    }
+    let hash = hex(&sha256(&text));
+    if Path::new(&manifest_path(problem_id, &hash)).exists() {
+        return Ok(hash);
+    }
+    // the version is linked aside and renamed into place; if a concurrent
+    // snapshot of the same testdata got there first, its copy is just as good
+    let dir = version_dir(problem_id, &hash);
+    let temp = format!("{}.{}", dir, Uuid::new_v4());
+    std::fs::create_dir_all(&temp)?;
+    for (name, blob) in files.iter() {
+        std::fs::hard_link(blob_path(blob), format!("{}/{}", temp, name))?;
+    }
+    if std::fs::rename(&temp, &dir).is_err() {
+        std::fs::remove_dir_all(&temp)?;
+        if !Path::new(&dir).is_dir() {
+            anyhow::bail!("failed to save testdata version `{}`", hash);
+        }
+    }
+    let version = Version {
+        hash: hash.clone(),
+        time: Utc::now().naive_local(),
+        files,
+    };
+    // the manifest is written last and marks the version as complete
+    write_atomic(
+        &manifest_path(problem_id, &hash),
+        serde_json::to_string(&version)?,
+    )?;
+    Ok(hash)
+}
+
+pub fn version(problem_id: Uuid, hash: &str) -> Result<Version> {
+    if !is_hash(hash) {
+        anyhow::bail!("`{}` is not a testdata version", hash);
+    }
+    let text = std::fs::read_to_string(manifest_path(problem_id, hash))
+        .with_context(|| format!("testdata version `{}` does not exist", hash))?;
+    Ok(serde_json::from_str(&text)?)
+}
+
+pub fn versions(problem_id: Uuid) -> Result<Vec<Version>> {
+    let mut versions = vec![];
+    if let Ok(dir) = std::fs::read_dir(history_dir(problem_id)) {
+        for entry in dir.filter_map(|x| x.ok()) {
+            let name = entry.file_name().into_string().unwrap_or_default();
+            if let Some(hash) = name.strip_suffix(".json") {
+                versions.push(version(problem_id, hash)?);
+            }
+        }
+    }
+    versions.sort_by_key(|x| x.time);
+    Ok(versions)
+}
+
+pub fn restore(problem_id: Uuid, hash: &str) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut files = vec![];
+    for (name, blob) in version(problem_id, hash)?.files {
+        files.push((name, std::fs::read(blob_path(&blob))?));
+    }
+    Ok(files)
+}
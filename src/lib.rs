@@ -1,4 +1,9 @@
+pub mod cli;
 pub mod judger;
+pub mod metrics;
+pub mod migration;
+pub mod package;
+pub mod similarity;
 pub mod sys;
 pub mod utils;
 pub mod web;
@@ -6,41 +11,283 @@ pub mod web;
 use crate::judger::Judger;
 use anyhow::{Context, Result};
 use axum::Server;
-use sea_orm::Database;
+use schemars::schema::{InstanceType, Schema, SingleOrVec};
+use schemars::JsonSchema;
+use sea_orm::{DatabaseConnection, SqlxMySqlConnector, SqlxPostgresConnector, SqlxSqliteConnector};
 use serde::{Deserialize, Serialize};
-use std::net::SocketAddr;
+use sqlx::{MySqlPool, PgPool, SqlitePool};
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
 use std::ptr::null_mut;
 use std::sync::atomic::AtomicPtr;
+use std::time::Duration;
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     pub web: ConfigWeb,
     pub db: ConfigDb,
     pub judger: ConfigJudger,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct ConfigWeb {
     pub root: String,
     pub host: String,
     pub port: u16,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct ConfigDb {
+    #[serde(default)]
+    pub driver: ConfigDbDriver,
+    // a full connection url, which takes precedence over the other fields
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default)]
     pub host: String,
+    #[serde(default)]
     pub username: String,
+    #[serde(default)]
     pub password: String,
     pub database: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigDbDriver {
+    #[default]
+    Mysql,
+    Postgres,
+    Sqlite,
+}
+
+impl ConfigDb {
+    pub fn url(&self) -> String {
+        if let Some(ref url) = self.url {
+            return url.clone();
+        }
+        match self.driver {
+            ConfigDbDriver::Mysql => format!(
+                "mysql://{}:{}@{}/{}",
+                self.username, self.password, self.host, self.database
+            ),
+            ConfigDbDriver::Postgres => format!(
+                "postgres://{}:{}@{}/{}",
+                self.username, self.password, self.host, self.database
+            ),
+            // `database` is the path of the file, which is created on first use
+            ConfigDbDriver::Sqlite => format!("sqlite://{}?mode=rwc", self.database),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct ConfigJudger {
     pub root: String,
     pub stack_limit: u64,
     pub output_limit: u64,
     pub compiler_c: String,
     pub compiler_cxx: String,
+    #[serde(default = "shutdown_timeout_default")]
+    pub shutdown_timeout: u64,
+}
+
+fn shutdown_timeout_default() -> u64 {
+    60
+}
+
+// keys whose values never leave the process in plain text
+const SECRETS: &[&str] = &["password", "url"];
+
+// `BOARDJUDGE_DB__PASSWORD` overrides `db.password`, `__` separates the levels
+const ENV_PREFIX: &str = "BOARDJUDGE_";
+
+impl Config {
+    // the file alone, as written
+    pub fn read(path: &Path) -> Result<toml::Value> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read config {:?}", path))?;
+        toml::from_str(&text).with_context(|| format!("failed to parse config {:?}", path))
+    }
+
+    // the file, then environment overrides, then `*_file` secrets
+    pub fn load(path: &Path) -> Result<Config> {
+        let config = Config::parse(path)?;
+        config.validate_db().context("invalid config")?;
+        Ok(config)
+    }
+
+    // for commands that never touch the database, such as `judge`
+    pub fn load_offline(path: &Path) -> Result<Config> {
+        Config::parse(path)
+    }
+
+    fn parse(path: &Path) -> Result<Config> {
+        let mut value = Config::read(path)?;
+        for (key, raw) in std::env::vars() {
+            if let Some(key) = key.strip_prefix(ENV_PREFIX).filter(|x| x.contains("__")) {
+                let path = key.to_lowercase();
+                let path = path.split("__").collect::<Vec<_>>();
+                override_value(&mut value, &path, &raw)
+                    .with_context(|| format!("failed to apply `{}{}`", ENV_PREFIX, key))?;
+            }
+        }
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        resolve_secrets(&mut value, dir, "")?;
+        let config: Config = value.try_into().context("invalid config")?;
+        config.validate().context("invalid config")?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        empty("web.root", &self.web.root)?;
+        if self.web.host.parse::<IpAddr>().is_err() {
+            anyhow::bail!("`web.host` is not an ip address: `{}`", self.web.host);
+        }
+        empty("judger.root", &self.judger.root)?;
+        empty("judger.compiler_c", &self.judger.compiler_c)?;
+        empty("judger.compiler_cxx", &self.judger.compiler_cxx)?;
+        if self.judger.stack_limit == 0 {
+            anyhow::bail!("`judger.stack_limit` must be positive");
+        }
+        if self.judger.output_limit == 0 {
+            anyhow::bail!("`judger.output_limit` must be positive");
+        }
+        Ok(())
+    }
+
+    pub fn validate_db(&self) -> Result<()> {
+        match self.db.url {
+            Some(ref url) => {
+                let scheme = url.split("://").next().unwrap_or_default();
+                if !["mysql", "mariadb", "postgres", "postgresql", "sqlite"].contains(&scheme) {
+                    anyhow::bail!("`db.url` has an unsupported scheme `{}`", scheme);
+                }
+            }
+            None => {
+                if self.db.driver != ConfigDbDriver::Sqlite {
+                    empty("db.host", &self.db.host)?;
+                    empty("db.username", &self.db.username)?;
+                }
+                empty("db.database", &self.db.database)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn empty(key: &str, value: &str) -> Result<()> {
+    if value.is_empty() {
+        anyhow::bail!("`{}` must not be empty", key);
+    }
+    Ok(())
+}
+
+// the raw value is read as the type serde expects there, keys unknown to `Config` stay strings
+fn override_value(value: &mut toml::Value, path: &[&str], raw: &str) -> Result<()> {
+    let (key, parents) = path.split_last().context("empty key")?;
+    let mut table = value.as_table_mut().context("config is not a table")?;
+    for parent in parents {
+        table = table
+            .entry(parent.to_string())
+            .or_insert_with(|| toml::Value::Table(Default::default()))
+            .as_table_mut()
+            .with_context(|| format!("`{}` is not a table", parent))?;
+    }
+    let new = match instance_type(path) {
+        Some(InstanceType::Integer) => toml::Value::Integer(
+            raw.parse()
+                .with_context(|| format!("`{}` is not an integer", raw))?,
+        ),
+        Some(InstanceType::Boolean) => toml::Value::Boolean(
+            raw.parse()
+                .with_context(|| format!("`{}` is not a boolean", raw))?,
+        ),
+        _ => toml::Value::String(raw.to_string()),
+    };
+    // the latest source wins over both spellings of a secret
+    match key.strip_suffix("_file") {
+        Some(plain) => table.remove(plain),
+        None => table.remove(&format!("{}_file", key)),
+    };
+    table.insert(key.to_string(), new);
+    Ok(())
+}
+
+fn instance_type(path: &[&str]) -> Option<InstanceType> {
+    let root = schemars::schema_for!(Config);
+    let mut schema = &root.schema;
+    for key in path {
+        schema = match schema.object.as_ref()?.properties.get(*key)? {
+            Schema::Object(x) => x,
+            Schema::Bool(_) => return None,
+        };
+        if let Some(ref reference) = schema.reference {
+            schema = match root.definitions.get(reference.rsplit('/').next()?)? {
+                Schema::Object(x) => x,
+                Schema::Bool(_) => return None,
+            };
+        }
+    }
+    match schema.instance_type.as_ref()? {
+        SingleOrVec::Single(x) => Some(**x),
+        SingleOrVec::Vec(x) => x.iter().copied().find(|x| *x != InstanceType::Null),
+    }
+}
+
+// `password_file = "/run/secrets/db"` becomes `password`, relative paths start at the config
+fn resolve_secrets(value: &mut toml::Value, dir: &Path, prefix: &str) -> Result<()> {
+    let table = match value.as_table_mut() {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    let keys = table.keys().cloned().collect::<Vec<_>>();
+    for key in keys {
+        let name = format!("{}{}", prefix, key);
+        if let Some(plain) = key.strip_suffix("_file") {
+            if table.contains_key(plain) {
+                anyhow::bail!("`{}{}` and `{}` are both set", prefix, plain, name);
+            }
+            let file = table
+                .remove(&key)
+                .and_then(|x| x.as_str().map(|x| dir.join(x)))
+                .with_context(|| format!("`{}` is not a path", name))?;
+            let secret = std::fs::read_to_string(&file)
+                .with_context(|| format!("failed to read `{}` from {:?}", name, file))?;
+            let secret = secret.trim_end_matches(['\r', '\n']).to_string();
+            table.insert(plain.to_string(), toml::Value::String(secret));
+        } else if let Some(child) = table.get_mut(&key) {
+            resolve_secrets(child, dir, &format!("{}.", name))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn redact(value: &mut toml::Value) {
+    if let Some(table) = value.as_table_mut() {
+        for (key, child) in table.iter_mut() {
+            match child {
+                toml::Value::String(x) if !x.is_empty() && SECRETS.contains(&key.as_str()) => {
+                    *x = match x.split_once("://") {
+                        // only the password of a url is hidden
+                        Some((scheme, rest)) => match rest.split_once('@') {
+                            Some((credentials, host)) => {
+                                let user = credentials.split(':').next().unwrap_or_default();
+                                format!("{}://{}:<redacted>@{}", scheme, user, host)
+                            }
+                            None => x.clone(),
+                        },
+                        None => "<redacted>".to_string(),
+                    }
+                }
+                _ => redact(child),
+            }
+        }
+    }
 }
 
 static CONFIG: AtomicPtr<Config> = AtomicPtr::new(null_mut());
@@ -54,48 +301,90 @@ pub fn config() -> &'static Config {
     unsafe { &(*ptr) }
 }
 
-pub async fn main(p: Config) -> Result<()> {
+pub fn init(p: Config) {
     use std::sync::atomic::Ordering;
     let p = Box::into_raw(Box::new(p));
     CONFIG
         .compare_exchange(null_mut(), p, Ordering::SeqCst, Ordering::SeqCst)
         .expect("boot twice");
-    let url = format!(
-        "mysql://{}:{}@{}/{}",
-        config().db.username,
-        config().db.password,
-        config().db.host,
-        config().db.database
-    );
-    let conn = Database::connect(url)
-        .await
-        .context("database connection failed")?;
-    let judger = Judger::daemon();
-    let web = tokio::spawn({
-        async move {
-            let router = self::web::router(conn, judger);
-            let addr = SocketAddr::new(
-                config()
-                    .web
-                    .host
-                    .parse()
-                    .context("failed to parse web.host")?,
-                config().web.port,
-            );
-            tracing::info!("listening on {}", addr);
-            Server::bind(&addr)
-                .serve(router.into_make_service())
+}
+
+pub async fn connect() -> Result<DatabaseConnection> {
+    let url = config().db.url();
+    let scheme = url.split(':').next().unwrap_or_default();
+    let conn = match scheme {
+        "mysql" | "mariadb" => {
+            let pool = MySqlPool::connect(&url)
                 .await
-                .context("failed to start a server")?;
-            Err(anyhow::anyhow!("server exited expectedly"))
+                .context("database connection failed")?;
+            metrics::watch(pool.clone());
+            SqlxMySqlConnector::from_sqlx_mysql_pool(pool)
         }
-    });
-    tokio::select! {
-        web = web => {
-            web??
+        "postgres" | "postgresql" => {
+            let pool = PgPool::connect(&url)
+                .await
+                .context("database connection failed")?;
+            metrics::watch(pool.clone());
+            SqlxPostgresConnector::from_sqlx_postgres_pool(pool)
         }
-        _ = tokio::signal::ctrl_c() => {
-            Err(anyhow::anyhow!("received ctrl-c event"))
+        "sqlite" => {
+            let pool = SqlitePool::connect(&url)
+                .await
+                .context("database connection failed")?;
+            metrics::watch(pool.clone());
+            SqlxSqliteConnector::from_sqlx_sqlite_pool(pool)
         }
+        _ => anyhow::bail!("unsupported database url scheme `{}`", scheme),
+    };
+    Ok(conn)
+}
+
+pub async fn main(migrate: bool) -> Result<()> {
+    let conn = connect().await?;
+    if migrate {
+        migration::up(&conn, None).await?;
+    }
+    let judger = Judger::daemon();
+    let resumed = self::web::api::submit::resume(&conn, &judger)
+        .await
+        .context("failed to resume waiting records")?;
+    if resumed != 0 {
+        tracing::info!("resumed {} waiting records", resumed);
+    }
+    let router = self::web::router(conn, judger.clone());
+    let addr = SocketAddr::new(
+        config()
+            .web
+            .host
+            .parse()
+            .context("failed to parse web.host")?,
+        config().web.port,
+    );
+    tracing::info!("listening on {}", addr);
+    Server::bind(&addr)
+        .serve(router.into_make_service())
+        .with_graceful_shutdown({
+            let judger = judger.clone();
+            async move {
+                shutdown().await;
+                tracing::info!("shutting down");
+                judger.stop();
+            }
+        })
+        .await
+        .context("failed to start a server")?;
+    let timeout = Duration::from_secs(config().judger.shutdown_timeout);
+    if !judger.drain(timeout).await {
+        tracing::warn!("running judge did not finish in {:?}", timeout);
+    }
+    Ok(())
+}
+
+async fn shutdown() {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut terminate = signal(SignalKind::terminate()).expect("failed to listen for SIGTERM");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
     }
 }
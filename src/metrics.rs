@@ -0,0 +1,117 @@
+use prometheus::{
+    exponential_buckets, histogram_opts, opts, Encoder, HistogramVec, IntCounter, IntCounterVec,
+    IntGauge, Registry, TextEncoder,
+};
+use sqlx::{Database, Pool};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+pub struct Metrics {
+    pub registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_duration: HistogramVec,
+    pub judge_queue: IntGauge,
+    pub judge_duration: HistogramVec,
+    pub verdicts: IntCounterVec,
+    pub compile_failures: IntCounterVec,
+    pub sandbox_errors: IntCounter,
+    pub db_connections: IntGauge,
+    pub db_idle: IntGauge,
+}
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(|| Metrics::new().expect("invalid metric definitions"))
+}
+
+impl Metrics {
+    fn new() -> prometheus::Result<Metrics> {
+        let registry = Registry::new_custom(Some("boardjudge".to_string()), None)?;
+        let metrics = Metrics {
+            http_requests: IntCounterVec::new(
+                opts!("http_requests_total", "HTTP requests by route and status"),
+                &["method", "route", "status"],
+            )?,
+            http_duration: HistogramVec::new(
+                histogram_opts!(
+                    "http_request_duration_seconds",
+                    "HTTP request latency by route",
+                    exponential_buckets(0.001, 2.0, 15)?
+                ),
+                &["method", "route"],
+            )?,
+            judge_queue: IntGauge::new("judge_queue_depth", "Judges waiting for the judger")?,
+            judge_duration: HistogramVec::new(
+                histogram_opts!(
+                    "judge_duration_seconds",
+                    "Time spent judging a submission by language",
+                    exponential_buckets(0.1, 2.0, 12)?
+                ),
+                &["language"],
+            )?,
+            verdicts: IntCounterVec::new(
+                opts!("judge_verdicts_total", "Judged submissions by status"),
+                &["status"],
+            )?,
+            compile_failures: IntCounterVec::new(
+                opts!("compile_failures_total", "Failed compilations by language"),
+                &["language"],
+            )?,
+            sandbox_errors: IntCounter::new(
+                "sandbox_internal_errors_total",
+                "Sandbox runs that failed inside libjudger",
+            )?,
+            db_connections: IntGauge::new("db_pool_connections", "Open database connections")?,
+            db_idle: IntGauge::new("db_pool_idle", "Idle database connections")?,
+            registry,
+        };
+        metrics
+            .registry
+            .register(Box::new(metrics.http_requests.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.http_duration.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.judge_queue.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.judge_duration.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.verdicts.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.compile_failures.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.sandbox_errors.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.db_connections.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.db_idle.clone()))?;
+        Ok(metrics)
+    }
+
+    pub fn encode(&self) -> String {
+        let mut buffer = vec![];
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+}
+
+// sea-orm does not expose its pool, so the gauges are sampled from the sqlx pool behind it
+pub fn watch<DB: Database>(pool: Pool<DB>) {
+    tokio::spawn(async move {
+        loop {
+            metrics().db_connections.set(pool.size() as i64);
+            metrics().db_idle.set(pool.num_idle() as i64);
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    });
+}
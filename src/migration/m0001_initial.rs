@@ -0,0 +1,220 @@
+use sea_orm::DbBackend;
+
+// the schema of the original `starter.sql`, so databases made from it are adopted as they are
+
+const MYSQL_UP: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS `user` (
+  `id` uuid NOT NULL DEFAULT uuid(),
+  `name` varchar(32) CHARACTER SET ascii NOT NULL,
+  `nick` varchar(64) COLLATE utf8mb4_unicode_ci NOT NULL,
+  `description` text COLLATE utf8mb4_unicode_ci NOT NULL,
+  `password` binary(32) NOT NULL,
+  `banned` tinyint(1) NOT NULL DEFAULT 0,
+  `root` tinyint(1) NOT NULL DEFAULT 0,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `UNIQUE` (`name`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci",
+    "CREATE TABLE IF NOT EXISTS `contest` (
+  `id` uuid NOT NULL DEFAULT uuid(),
+  `nick` varchar(64) COLLATE utf8mb4_unicode_ci NOT NULL,
+  `description` text COLLATE utf8mb4_unicode_ci NOT NULL,
+  `start` datetime NOT NULL,
+  `end` datetime NOT NULL,
+  PRIMARY KEY (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci",
+    "CREATE TABLE IF NOT EXISTS `privilege` (
+  `id` uuid NOT NULL DEFAULT uuid(),
+  `user_id` uuid NOT NULL,
+  `contest_id` uuid NOT NULL,
+  `kind` varchar(32) COLLATE utf8mb4_unicode_ci NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `privilege_contest_id` (`contest_id`),
+  KEY `privilege_user_id` (`user_id`),
+  CONSTRAINT `privilege_contest_id` FOREIGN KEY (`contest_id`) REFERENCES `contest` (`id`) ON DELETE CASCADE ON UPDATE CASCADE,
+  CONSTRAINT `privilege_user_id` FOREIGN KEY (`user_id`) REFERENCES `user` (`id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci",
+    "CREATE TABLE IF NOT EXISTS `problem` (
+  `id` uuid NOT NULL DEFAULT uuid(),
+  `nick` varchar(64) COLLATE utf8mb4_unicode_ci NOT NULL,
+  `description` text COLLATE utf8mb4_unicode_ci NOT NULL,
+  `limit_time` int(10) unsigned NOT NULL,
+  `limit_memory` int(10) unsigned NOT NULL,
+  `contest_id` uuid NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `problem_contest_id` (`contest_id`),
+  CONSTRAINT `problem_contest_id` FOREIGN KEY (`contest_id`) REFERENCES `contest` (`id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci",
+    "CREATE TABLE IF NOT EXISTS `record` (
+  `id` uuid NOT NULL DEFAULT uuid(),
+  `time` datetime NOT NULL,
+  `user_id` uuid NOT NULL,
+  `problem_id` uuid NOT NULL,
+  `code` text COLLATE utf8mb4_unicode_ci NOT NULL,
+  `language` varchar(32) COLLATE utf8mb4_unicode_ci NOT NULL,
+  `result` longtext CHARACTER SET utf8mb4 COLLATE utf8mb4_bin NOT NULL CHECK (json_valid(`result`)),
+  `status` varchar(32) COLLATE utf8mb4_unicode_ci NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `record_problem_id` (`problem_id`),
+  KEY `record_user_id` (`user_id`),
+  CONSTRAINT `record_problem_id` FOREIGN KEY (`problem_id`) REFERENCES `problem` (`id`) ON DELETE CASCADE ON UPDATE CASCADE,
+  CONSTRAINT `record_user_id` FOREIGN KEY (`user_id`) REFERENCES `user` (`id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci",
+];
+
+const MYSQL_DOWN: &[&str] = &[
+    "DROP TABLE `record`",
+    "DROP TABLE `problem`",
+    "DROP TABLE `privilege`",
+    "DROP TABLE `contest`",
+    "DROP TABLE `user`",
+];
+
+const POSTGRES_UP: &[&str] = &[
+    r#"CREATE TABLE IF NOT EXISTS "user" (
+  "id" varchar(36) NOT NULL,
+  "name" varchar(32) NOT NULL,
+  "nick" varchar(64) NOT NULL,
+  "description" text NOT NULL,
+  "password" bytea NOT NULL,
+  "banned" boolean NOT NULL DEFAULT false,
+  "root" boolean NOT NULL DEFAULT false,
+  PRIMARY KEY ("id"),
+  CONSTRAINT "user_name" UNIQUE ("name")
+)"#,
+    r#"CREATE TABLE IF NOT EXISTS "contest" (
+  "id" varchar(36) NOT NULL,
+  "nick" varchar(64) NOT NULL,
+  "description" text NOT NULL,
+  "start" timestamp NOT NULL,
+  "end" timestamp NOT NULL,
+  PRIMARY KEY ("id")
+)"#,
+    r#"CREATE TABLE IF NOT EXISTS "privilege" (
+  "id" varchar(36) NOT NULL,
+  "user_id" varchar(36) NOT NULL,
+  "contest_id" varchar(36) NOT NULL,
+  "kind" varchar(32) NOT NULL,
+  PRIMARY KEY ("id"),
+  CONSTRAINT "privilege_contest_id" FOREIGN KEY ("contest_id") REFERENCES "contest" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+  CONSTRAINT "privilege_user_id" FOREIGN KEY ("user_id") REFERENCES "user" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+)"#,
+    r#"CREATE INDEX IF NOT EXISTS "privilege_contest_id" ON "privilege" ("contest_id")"#,
+    r#"CREATE INDEX IF NOT EXISTS "privilege_user_id" ON "privilege" ("user_id")"#,
+    r#"CREATE TABLE IF NOT EXISTS "problem" (
+  "id" varchar(36) NOT NULL,
+  "nick" varchar(64) NOT NULL,
+  "description" text NOT NULL,
+  "limit_time" bigint NOT NULL,
+  "limit_memory" bigint NOT NULL,
+  "contest_id" varchar(36) NOT NULL,
+  PRIMARY KEY ("id"),
+  CONSTRAINT "problem_contest_id" FOREIGN KEY ("contest_id") REFERENCES "contest" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+)"#,
+    r#"CREATE INDEX IF NOT EXISTS "problem_contest_id" ON "problem" ("contest_id")"#,
+    r#"CREATE TABLE IF NOT EXISTS "record" (
+  "id" varchar(36) NOT NULL,
+  "time" timestamp NOT NULL,
+  "user_id" varchar(36) NOT NULL,
+  "problem_id" varchar(36) NOT NULL,
+  "code" text NOT NULL,
+  "language" varchar(32) NOT NULL,
+  "result" text NOT NULL,
+  "status" varchar(32) NOT NULL,
+  PRIMARY KEY ("id"),
+  CONSTRAINT "record_problem_id" FOREIGN KEY ("problem_id") REFERENCES "problem" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+  CONSTRAINT "record_user_id" FOREIGN KEY ("user_id") REFERENCES "user" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+)"#,
+    r#"CREATE INDEX IF NOT EXISTS "record_problem_id" ON "record" ("problem_id")"#,
+    r#"CREATE INDEX IF NOT EXISTS "record_user_id" ON "record" ("user_id")"#,
+];
+
+const POSTGRES_DOWN: &[&str] = &[
+    r#"DROP TABLE "record""#,
+    r#"DROP TABLE "problem""#,
+    r#"DROP TABLE "privilege""#,
+    r#"DROP TABLE "contest""#,
+    r#"DROP TABLE "user""#,
+];
+
+const SQLITE_UP: &[&str] = &[
+    r#"CREATE TABLE IF NOT EXISTS "user" (
+  "id" varchar(36) NOT NULL,
+  "name" varchar(32) NOT NULL,
+  "nick" varchar(64) NOT NULL,
+  "description" text NOT NULL,
+  "password" blob NOT NULL,
+  "banned" boolean NOT NULL DEFAULT 0,
+  "root" boolean NOT NULL DEFAULT 0,
+  PRIMARY KEY ("id"),
+  CONSTRAINT "user_name" UNIQUE ("name")
+)"#,
+    r#"CREATE TABLE IF NOT EXISTS "contest" (
+  "id" varchar(36) NOT NULL,
+  "nick" varchar(64) NOT NULL,
+  "description" text NOT NULL,
+  "start" datetime NOT NULL,
+  "end" datetime NOT NULL,
+  PRIMARY KEY ("id")
+)"#,
+    r#"CREATE TABLE IF NOT EXISTS "privilege" (
+  "id" varchar(36) NOT NULL,
+  "user_id" varchar(36) NOT NULL,
+  "contest_id" varchar(36) NOT NULL,
+  "kind" varchar(32) NOT NULL,
+  PRIMARY KEY ("id"),
+  CONSTRAINT "privilege_contest_id" FOREIGN KEY ("contest_id") REFERENCES "contest" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+  CONSTRAINT "privilege_user_id" FOREIGN KEY ("user_id") REFERENCES "user" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+)"#,
+    r#"CREATE INDEX IF NOT EXISTS "privilege_contest_id" ON "privilege" ("contest_id")"#,
+    r#"CREATE INDEX IF NOT EXISTS "privilege_user_id" ON "privilege" ("user_id")"#,
+    r#"CREATE TABLE IF NOT EXISTS "problem" (
+  "id" varchar(36) NOT NULL,
+  "nick" varchar(64) NOT NULL,
+  "description" text NOT NULL,
+  "limit_time" bigint NOT NULL,
+  "limit_memory" bigint NOT NULL,
+  "contest_id" varchar(36) NOT NULL,
+  PRIMARY KEY ("id"),
+  CONSTRAINT "problem_contest_id" FOREIGN KEY ("contest_id") REFERENCES "contest" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+)"#,
+    r#"CREATE INDEX IF NOT EXISTS "problem_contest_id" ON "problem" ("contest_id")"#,
+    r#"CREATE TABLE IF NOT EXISTS "record" (
+  "id" varchar(36) NOT NULL,
+  "time" datetime NOT NULL,
+  "user_id" varchar(36) NOT NULL,
+  "problem_id" varchar(36) NOT NULL,
+  "code" text NOT NULL,
+  "language" varchar(32) NOT NULL,
+  "result" text NOT NULL,
+  "status" varchar(32) NOT NULL,
+  PRIMARY KEY ("id"),
+  CONSTRAINT "record_problem_id" FOREIGN KEY ("problem_id") REFERENCES "problem" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+  CONSTRAINT "record_user_id" FOREIGN KEY ("user_id") REFERENCES "user" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+)"#,
+    r#"CREATE INDEX IF NOT EXISTS "record_problem_id" ON "record" ("problem_id")"#,
+    r#"CREATE INDEX IF NOT EXISTS "record_user_id" ON "record" ("user_id")"#,
+];
+
+const SQLITE_DOWN: &[&str] = &[
+    r#"DROP TABLE "record""#,
+    r#"DROP TABLE "problem""#,
+    r#"DROP TABLE "privilege""#,
+    r#"DROP TABLE "contest""#,
+    r#"DROP TABLE "user""#,
+];
+
+pub fn up(backend: DbBackend) -> &'static [&'static str] {
+    match backend {
+        DbBackend::MySql => MYSQL_UP,
+        DbBackend::Postgres => POSTGRES_UP,
+        DbBackend::Sqlite => SQLITE_UP,
+    }
+}
+
+pub fn down(backend: DbBackend) -> &'static [&'static str] {
+    match backend {
+        DbBackend::MySql => MYSQL_DOWN,
+        DbBackend::Postgres => POSTGRES_DOWN,
+        DbBackend::Sqlite => SQLITE_DOWN,
+    }
+}
@@ -0,0 +1,69 @@
+use sea_orm::DbBackend;
+
+const MYSQL_UP: &[&str] = &["CREATE TABLE IF NOT EXISTS `token` (
+  `id` uuid NOT NULL DEFAULT uuid(),
+  `user_id` uuid NOT NULL,
+  `name` varchar(64) COLLATE utf8mb4_unicode_ci NOT NULL,
+  `hash` varchar(64) CHARACTER SET ascii NOT NULL,
+  `scope` varchar(32) COLLATE utf8mb4_unicode_ci NOT NULL,
+  `created` datetime NOT NULL,
+  `expires` datetime DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `token_hash` (`hash`),
+  KEY `token_user_id` (`user_id`),
+  CONSTRAINT `token_user_id` FOREIGN KEY (`user_id`) REFERENCES `user` (`id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci"];
+
+const MYSQL_DOWN: &[&str] = &["DROP TABLE `token`"];
+
+const POSTGRES_UP: &[&str] = &[
+    r#"CREATE TABLE IF NOT EXISTS "token" (
+  "id" varchar(36) NOT NULL,
+  "user_id" varchar(36) NOT NULL,
+  "name" varchar(64) NOT NULL,
+  "hash" varchar(64) NOT NULL,
+  "scope" varchar(32) NOT NULL,
+  "created" timestamp NOT NULL,
+  "expires" timestamp DEFAULT NULL,
+  PRIMARY KEY ("id"),
+  CONSTRAINT "token_hash" UNIQUE ("hash"),
+  CONSTRAINT "token_user_id" FOREIGN KEY ("user_id") REFERENCES "user" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+)"#,
+    r#"CREATE INDEX IF NOT EXISTS "token_user_id" ON "token" ("user_id")"#,
+];
+
+const POSTGRES_DOWN: &[&str] = &[r#"DROP TABLE "token""#];
+
+const SQLITE_UP: &[&str] = &[
+    r#"CREATE TABLE IF NOT EXISTS "token" (
+  "id" varchar(36) NOT NULL,
+  "user_id" varchar(36) NOT NULL,
+  "name" varchar(64) NOT NULL,
+  "hash" varchar(64) NOT NULL,
+  "scope" varchar(32) NOT NULL,
+  "created" datetime NOT NULL,
+  "expires" datetime DEFAULT NULL,
+  PRIMARY KEY ("id"),
+  CONSTRAINT "token_hash" UNIQUE ("hash"),
+  CONSTRAINT "token_user_id" FOREIGN KEY ("user_id") REFERENCES "user" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+)"#,
+    r#"CREATE INDEX IF NOT EXISTS "token_user_id" ON "token" ("user_id")"#,
+];
+
+const SQLITE_DOWN: &[&str] = &[r#"DROP TABLE "token""#];
+
+pub fn up(backend: DbBackend) -> &'static [&'static str] {
+    match backend {
+        DbBackend::MySql => MYSQL_UP,
+        DbBackend::Postgres => POSTGRES_UP,
+        DbBackend::Sqlite => SQLITE_UP,
+    }
+}
+
+pub fn down(backend: DbBackend) -> &'static [&'static str] {
+    match backend {
+        DbBackend::MySql => MYSQL_DOWN,
+        DbBackend::Postgres => POSTGRES_DOWN,
+        DbBackend::Sqlite => SQLITE_DOWN,
+    }
+}
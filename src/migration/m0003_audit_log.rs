@@ -0,0 +1,69 @@
+use sea_orm::DbBackend;
+
+const MYSQL_UP: &[&str] = &["CREATE TABLE IF NOT EXISTS `audit_log` (
+  `id` uuid NOT NULL DEFAULT uuid(),
+  `time` datetime NOT NULL,
+  `user_id` uuid NOT NULL,
+  `action` varchar(32) COLLATE utf8mb4_unicode_ci NOT NULL,
+  `target` uuid NOT NULL,
+  `before` longtext CHARACTER SET utf8mb4 COLLATE utf8mb4_bin DEFAULT NULL CHECK (json_valid(`before`)),
+  `after` longtext CHARACTER SET utf8mb4 COLLATE utf8mb4_bin DEFAULT NULL CHECK (json_valid(`after`)),
+  PRIMARY KEY (`id`),
+  KEY `audit_log_user_id` (`user_id`),
+  KEY `audit_log_target` (`target`),
+  KEY `audit_log_time` (`time`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci"];
+
+const MYSQL_DOWN: &[&str] = &["DROP TABLE `audit_log`"];
+
+const POSTGRES_UP: &[&str] = &[
+    r#"CREATE TABLE IF NOT EXISTS "audit_log" (
+  "id" varchar(36) NOT NULL,
+  "time" timestamp NOT NULL,
+  "user_id" varchar(36) NOT NULL,
+  "action" varchar(32) NOT NULL,
+  "target" varchar(36) NOT NULL,
+  "before" text DEFAULT NULL,
+  "after" text DEFAULT NULL,
+  PRIMARY KEY ("id")
+)"#,
+    r#"CREATE INDEX IF NOT EXISTS "audit_log_user_id" ON "audit_log" ("user_id")"#,
+    r#"CREATE INDEX IF NOT EXISTS "audit_log_target" ON "audit_log" ("target")"#,
+    r#"CREATE INDEX IF NOT EXISTS "audit_log_time" ON "audit_log" ("time")"#,
+];
+
+const POSTGRES_DOWN: &[&str] = &[r#"DROP TABLE "audit_log""#];
+
+const SQLITE_UP: &[&str] = &[
+    r#"CREATE TABLE IF NOT EXISTS "audit_log" (
+  "id" varchar(36) NOT NULL,
+  "time" datetime NOT NULL,
+  "user_id" varchar(36) NOT NULL,
+  "action" varchar(32) NOT NULL,
+  "target" varchar(36) NOT NULL,
+  "before" text DEFAULT NULL,
+  "after" text DEFAULT NULL,
+  PRIMARY KEY ("id")
+)"#,
+    r#"CREATE INDEX IF NOT EXISTS "audit_log_user_id" ON "audit_log" ("user_id")"#,
+    r#"CREATE INDEX IF NOT EXISTS "audit_log_target" ON "audit_log" ("target")"#,
+    r#"CREATE INDEX IF NOT EXISTS "audit_log_time" ON "audit_log" ("time")"#,
+];
+
+const SQLITE_DOWN: &[&str] = &[r#"DROP TABLE "audit_log""#];
+
+pub fn up(backend: DbBackend) -> &'static [&'static str] {
+    match backend {
+        DbBackend::MySql => MYSQL_UP,
+        DbBackend::Postgres => POSTGRES_UP,
+        DbBackend::Sqlite => SQLITE_UP,
+    }
+}
+
+pub fn down(backend: DbBackend) -> &'static [&'static str] {
+    match backend {
+        DbBackend::MySql => MYSQL_DOWN,
+        DbBackend::Postgres => POSTGRES_DOWN,
+        DbBackend::Sqlite => SQLITE_DOWN,
+    }
+}
@@ -0,0 +1,66 @@
+use sea_orm::DbBackend;
+
+const MYSQL_UP: &[&str] = &["CREATE TABLE IF NOT EXISTS `similarity` (
+  `id` uuid NOT NULL DEFAULT uuid(),
+  `time` datetime NOT NULL,
+  `user_id` uuid NOT NULL,
+  `contest_id` uuid NOT NULL,
+  `problem_id` uuid DEFAULT NULL,
+  `status` varchar(32) COLLATE utf8mb4_unicode_ci NOT NULL,
+  `report` longtext CHARACTER SET utf8mb4 COLLATE utf8mb4_bin NOT NULL CHECK (json_valid(`report`)),
+  PRIMARY KEY (`id`),
+  KEY `similarity_contest_id` (`contest_id`),
+  CONSTRAINT `similarity_contest_id` FOREIGN KEY (`contest_id`) REFERENCES `contest` (`id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci"];
+
+const MYSQL_DOWN: &[&str] = &["DROP TABLE `similarity`"];
+
+const POSTGRES_UP: &[&str] = &[
+    r#"CREATE TABLE IF NOT EXISTS "similarity" (
+  "id" varchar(36) NOT NULL,
+  "time" timestamp NOT NULL,
+  "user_id" varchar(36) NOT NULL,
+  "contest_id" varchar(36) NOT NULL,
+  "problem_id" varchar(36) DEFAULT NULL,
+  "status" varchar(32) NOT NULL,
+  "report" text NOT NULL,
+  PRIMARY KEY ("id"),
+  CONSTRAINT "similarity_contest_id" FOREIGN KEY ("contest_id") REFERENCES "contest" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+)"#,
+    r#"CREATE INDEX IF NOT EXISTS "similarity_contest_id" ON "similarity" ("contest_id")"#,
+];
+
+const POSTGRES_DOWN: &[&str] = &[r#"DROP TABLE "similarity""#];
+
+const SQLITE_UP: &[&str] = &[
+    r#"CREATE TABLE IF NOT EXISTS "similarity" (
+  "id" varchar(36) NOT NULL,
+  "time" datetime NOT NULL,
+  "user_id" varchar(36) NOT NULL,
+  "contest_id" varchar(36) NOT NULL,
+  "problem_id" varchar(36) DEFAULT NULL,
+  "status" varchar(32) NOT NULL,
+  "report" text NOT NULL,
+  PRIMARY KEY ("id"),
+  CONSTRAINT "similarity_contest_id" FOREIGN KEY ("contest_id") REFERENCES "contest" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+)"#,
+    r#"CREATE INDEX IF NOT EXISTS "similarity_contest_id" ON "similarity" ("contest_id")"#,
+];
+
+const SQLITE_DOWN: &[&str] = &[r#"DROP TABLE "similarity""#];
+
+pub fn up(backend: DbBackend) -> &'static [&'static str] {
+    match backend {
+        DbBackend::MySql => MYSQL_UP,
+        DbBackend::Postgres => POSTGRES_UP,
+        DbBackend::Sqlite => SQLITE_UP,
+    }
+}
+
+pub fn down(backend: DbBackend) -> &'static [&'static str] {
+    match backend {
+        DbBackend::MySql => MYSQL_DOWN,
+        DbBackend::Postgres => POSTGRES_DOWN,
+        DbBackend::Sqlite => SQLITE_DOWN,
+    }
+}
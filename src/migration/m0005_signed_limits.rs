@@ -0,0 +1,24 @@
+use sea_orm::DbBackend;
+
+// unsigned columns cannot be decoded by every driver, the other backends start out signed
+const MYSQL_UP: &[&str] = &["ALTER TABLE `problem`
+  MODIFY `limit_time` bigint(20) NOT NULL,
+  MODIFY `limit_memory` bigint(20) NOT NULL"];
+
+const MYSQL_DOWN: &[&str] = &["ALTER TABLE `problem`
+  MODIFY `limit_time` int(10) unsigned NOT NULL,
+  MODIFY `limit_memory` int(10) unsigned NOT NULL"];
+
+pub fn up(backend: DbBackend) -> &'static [&'static str] {
+    match backend {
+        DbBackend::MySql => MYSQL_UP,
+        DbBackend::Postgres | DbBackend::Sqlite => &[],
+    }
+}
+
+pub fn down(backend: DbBackend) -> &'static [&'static str] {
+    match backend {
+        DbBackend::MySql => MYSQL_DOWN,
+        DbBackend::Postgres | DbBackend::Sqlite => &[],
+    }
+}
@@ -0,0 +1,73 @@
+use sea_orm::DbBackend;
+
+// the columns added to the baseline by problem kinds, file io and testdata versions
+const MYSQL_UP: &[&str] = &[
+    "ALTER TABLE `problem`
+  ADD COLUMN IF NOT EXISTS `kind` varchar(32) COLLATE utf8mb4_unicode_ci NOT NULL DEFAULT 'traditional',
+  ADD COLUMN IF NOT EXISTS `input_file` varchar(64) COLLATE utf8mb4_unicode_ci DEFAULT NULL,
+  ADD COLUMN IF NOT EXISTS `output_file` varchar(64) COLLATE utf8mb4_unicode_ci DEFAULT NULL,
+  ADD COLUMN IF NOT EXISTS `testdata` varchar(64) CHARACTER SET ascii NOT NULL DEFAULT ''",
+    "ALTER TABLE `record`
+  ADD COLUMN IF NOT EXISTS `testdata` varchar(64) CHARACTER SET ascii NOT NULL DEFAULT ''",
+];
+
+const MYSQL_DOWN: &[&str] = &[
+    "ALTER TABLE `record` DROP COLUMN `testdata`",
+    "ALTER TABLE `problem`
+  DROP COLUMN `testdata`,
+  DROP COLUMN `output_file`,
+  DROP COLUMN `input_file`,
+  DROP COLUMN `kind`",
+];
+
+const POSTGRES_UP: &[&str] = &[
+    r#"ALTER TABLE "problem"
+  ADD COLUMN IF NOT EXISTS "kind" varchar(32) NOT NULL DEFAULT 'traditional',
+  ADD COLUMN IF NOT EXISTS "input_file" varchar(64) DEFAULT NULL,
+  ADD COLUMN IF NOT EXISTS "output_file" varchar(64) DEFAULT NULL,
+  ADD COLUMN IF NOT EXISTS "testdata" varchar(64) NOT NULL DEFAULT ''"#,
+    r#"ALTER TABLE "record"
+  ADD COLUMN IF NOT EXISTS "testdata" varchar(64) NOT NULL DEFAULT ''"#,
+];
+
+const POSTGRES_DOWN: &[&str] = &[
+    r#"ALTER TABLE "record" DROP COLUMN "testdata""#,
+    r#"ALTER TABLE "problem"
+  DROP COLUMN "testdata",
+  DROP COLUMN "output_file",
+  DROP COLUMN "input_file",
+  DROP COLUMN "kind""#,
+];
+
+// sqlite alters one column per statement
+const SQLITE_UP: &[&str] = &[
+    r#"ALTER TABLE "problem" ADD COLUMN "kind" varchar(32) NOT NULL DEFAULT 'traditional'"#,
+    r#"ALTER TABLE "problem" ADD COLUMN "input_file" varchar(64) DEFAULT NULL"#,
+    r#"ALTER TABLE "problem" ADD COLUMN "output_file" varchar(64) DEFAULT NULL"#,
+    r#"ALTER TABLE "problem" ADD COLUMN "testdata" varchar(64) NOT NULL DEFAULT ''"#,
+    r#"ALTER TABLE "record" ADD COLUMN "testdata" varchar(64) NOT NULL DEFAULT ''"#,
+];
+
+const SQLITE_DOWN: &[&str] = &[
+    r#"ALTER TABLE "record" DROP COLUMN "testdata""#,
+    r#"ALTER TABLE "problem" DROP COLUMN "testdata""#,
+    r#"ALTER TABLE "problem" DROP COLUMN "output_file""#,
+    r#"ALTER TABLE "problem" DROP COLUMN "input_file""#,
+    r#"ALTER TABLE "problem" DROP COLUMN "kind""#,
+];
+
+pub fn up(backend: DbBackend) -> &'static [&'static str] {
+    match backend {
+        DbBackend::MySql => MYSQL_UP,
+        DbBackend::Postgres => POSTGRES_UP,
+        DbBackend::Sqlite => SQLITE_UP,
+    }
+}
+
+pub fn down(backend: DbBackend) -> &'static [&'static str] {
+    match backend {
+        DbBackend::MySql => MYSQL_DOWN,
+        DbBackend::Postgres => POSTGRES_DOWN,
+        DbBackend::Sqlite => SQLITE_DOWN,
+    }
+}
@@ -0,0 +1,33 @@
+use sea_orm::DbBackend;
+
+// observers only see the records submitted before the freeze
+const MYSQL_UP: &[&str] = &["ALTER TABLE `contest`
+  ADD COLUMN IF NOT EXISTS `freeze_time` datetime DEFAULT NULL"];
+
+const MYSQL_DOWN: &[&str] = &["ALTER TABLE `contest` DROP COLUMN `freeze_time`"];
+
+const POSTGRES_UP: &[&str] = &[r#"ALTER TABLE "contest"
+  ADD COLUMN IF NOT EXISTS "freeze_time" timestamp DEFAULT NULL"#];
+
+const POSTGRES_DOWN: &[&str] = &[r#"ALTER TABLE "contest" DROP COLUMN "freeze_time""#];
+
+const SQLITE_UP: &[&str] =
+    &[r#"ALTER TABLE "contest" ADD COLUMN "freeze_time" datetime DEFAULT NULL"#];
+
+const SQLITE_DOWN: &[&str] = &[r#"ALTER TABLE "contest" DROP COLUMN "freeze_time""#];
+
+pub fn up(backend: DbBackend) -> &'static [&'static str] {
+    match backend {
+        DbBackend::MySql => MYSQL_UP,
+        DbBackend::Postgres => POSTGRES_UP,
+        DbBackend::Sqlite => SQLITE_UP,
+    }
+}
+
+pub fn down(backend: DbBackend) -> &'static [&'static str] {
+    match backend {
+        DbBackend::MySql => MYSQL_DOWN,
+        DbBackend::Postgres => POSTGRES_DOWN,
+        DbBackend::Sqlite => SQLITE_DOWN,
+    }
+}
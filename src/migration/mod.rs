@@ -0,0 +1,204 @@
+pub mod m0001_initial;
+pub mod m0002_token;
+pub mod m0003_audit_log;
+pub mod m0004_similarity;
+pub mod m0005_signed_limits;
+pub mod m0006_problem_options;
+pub mod m0007_contest_freeze;
+
+#[cfg(test)]
+mod tests;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use sea_orm::sea_query::{Alias, ColumnDef, Expr, Order, Query, Table};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Statement, TransactionTrait};
+use std::collections::BTreeMap;
+
+// the same table as sea-orm-migration, so switching to it later keeps the history
+const TABLE: &str = "seaql_migrations";
+
+pub struct Migration {
+    pub name: &'static str,
+    pub up: fn(DbBackend) -> &'static [&'static str],
+    pub down: fn(DbBackend) -> &'static [&'static str],
+}
+
+// append only, a released migration is never edited
+pub fn migrations() -> Vec<Migration> {
+    vec![
+        Migration {
+            name: "m0001_initial",
+            up: m0001_initial::up,
+            down: m0001_initial::down,
+        },
+        Migration {
+            name: "m0002_token",
+            up: m0002_token::up,
+            down: m0002_token::down,
+        },
+        Migration {
+            name: "m0003_audit_log",
+            up: m0003_audit_log::up,
+            down: m0003_audit_log::down,
+        },
+        Migration {
+            name: "m0004_similarity",
+            up: m0004_similarity::up,
+            down: m0004_similarity::down,
+        },
+        Migration {
+            name: "m0005_signed_limits",
+            up: m0005_signed_limits::up,
+            down: m0005_signed_limits::down,
+        },
+        Migration {
+            name: "m0006_problem_options",
+            up: m0006_problem_options::up,
+            down: m0006_problem_options::down,
+        },
+        Migration {
+            name: "m0007_contest_freeze",
+            up: m0007_contest_freeze::up,
+            down: m0007_contest_freeze::down,
+        },
+    ]
+}
+
+async fn prepare(conn: &DatabaseConnection) -> Result<()> {
+    let statement = Table::create()
+        .table(Alias::new(TABLE))
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Alias::new("version"))
+                .string_len(255)
+                .not_null()
+                .primary_key(),
+        )
+        .col(
+            ColumnDef::new(Alias::new("applied_at"))
+                .big_integer()
+                .not_null(),
+        )
+        .to_owned();
+    conn.execute(conn.get_database_backend().build(&statement))
+        .await
+        .context("failed to create the migration table")?;
+    Ok(())
+}
+
+async fn applied(conn: &DatabaseConnection) -> Result<BTreeMap<String, i64>> {
+    prepare(conn).await?;
+    let statement = Query::select()
+        .columns([Alias::new("version"), Alias::new("applied_at")])
+        .from(Alias::new(TABLE))
+        .order_by(Alias::new("version"), Order::Asc)
+        .to_owned();
+    let mut result = BTreeMap::new();
+    for row in conn
+        .query_all(conn.get_database_backend().build(&statement))
+        .await?
+    {
+        result.insert(row.try_get("", "version")?, row.try_get("", "applied_at")?);
+    }
+    Ok(result)
+}
+
+async fn execute<C: ConnectionTrait>(conn: &C, name: &str, statements: &[&str]) -> Result<()> {
+    for sql in statements {
+        let statement = Statement::from_string(conn.get_database_backend(), sql.to_string());
+        conn.execute(statement)
+            .await
+            .with_context(|| format!("migration `{}` failed", name))?;
+    }
+    Ok(())
+}
+
+// the statements of a migration and its row in the migration table go in one transaction, but
+// mysql commits every ddl statement on its own, so a migration failing there halfway is left
+// half applied and has to be repaired by hand
+async fn apply(
+    conn: &DatabaseConnection,
+    name: &str,
+    statements: &[&str],
+    history: Statement,
+) -> Result<()> {
+    if conn.get_database_backend() == DbBackend::MySql {
+        execute(conn, name, statements).await.with_context(|| {
+            format!(
+                "mysql cannot roll back `{}`, repair the schema and `{}` by hand",
+                name, TABLE
+            )
+        })?;
+        conn.execute(history).await?;
+        return Ok(());
+    }
+    let txn = conn.begin().await?;
+    execute(&txn, name, statements).await?;
+    txn.execute(history).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+// every migration with the unix time it was applied at, if it was
+pub async fn status(conn: &DatabaseConnection) -> Result<Vec<(&'static str, Option<i64>)>> {
+    let applied = applied(conn).await?;
+    Ok(migrations()
+        .into_iter()
+        .map(|x| (x.name, applied.get(x.name).copied()))
+        .collect())
+}
+
+// applies at most `num` pending migrations, or all of them
+pub async fn up(conn: &DatabaseConnection, num: Option<usize>) -> Result<Vec<&'static str>> {
+    let applied = applied(conn).await?;
+    let mut done = vec![];
+    for migration in migrations()
+        .into_iter()
+        .filter(|x| !applied.contains_key(x.name))
+        .take(num.unwrap_or(usize::MAX))
+    {
+        tracing::info!("applying migration `{}`", migration.name);
+        let statement = Query::insert()
+            .into_table(Alias::new(TABLE))
+            .columns([Alias::new("version"), Alias::new("applied_at")])
+            .values_panic([migration.name.into(), Utc::now().timestamp().into()])
+            .to_owned();
+        apply(
+            conn,
+            migration.name,
+            (migration.up)(conn.get_database_backend()),
+            conn.get_database_backend().build(&statement),
+        )
+        .await?;
+        done.push(migration.name);
+    }
+    Ok(done)
+}
+
+// rolls back the last `num` applied migrations
+pub async fn down(conn: &DatabaseConnection, num: usize) -> Result<Vec<&'static str>> {
+    let applied = applied(conn).await?;
+    let mut done = vec![];
+    for migration in migrations()
+        .into_iter()
+        .rev()
+        .filter(|x| applied.contains_key(x.name))
+        .take(num)
+    {
+        tracing::info!("rolling back migration `{}`", migration.name);
+        let statement = Query::delete()
+            .from_table(Alias::new(TABLE))
+            .and_where(Expr::col(Alias::new("version")).eq(migration.name))
+            .to_owned();
+        apply(
+            conn,
+            migration.name,
+            (migration.down)(conn.get_database_backend()),
+            conn.get_database_backend().build(&statement),
+        )
+        .await?;
+        done.push(migration.name);
+    }
+    Ok(done)
+}
@@ -0,0 +1,40 @@
+use super::*;
+use sea_orm::{ConnectOptions, Database};
+
+async fn database() -> DatabaseConnection {
+    let mut options = ConnectOptions::new("sqlite::memory:".to_string());
+    options.max_connections(1);
+    Database::connect(options).await.unwrap()
+}
+
+fn history(name: &str) -> Statement {
+    let statement = Query::insert()
+        .into_table(Alias::new(TABLE))
+        .columns([Alias::new("version"), Alias::new("applied_at")])
+        .values_panic([name.into(), 0.into()])
+        .to_owned();
+    DbBackend::Sqlite.build(&statement)
+}
+
+#[tokio::test]
+async fn migrations_go_up_and_down() {
+    let conn = database().await;
+    let names = migrations().into_iter().map(|x| x.name).collect::<Vec<_>>();
+    assert_eq!(up(&conn, None).await.unwrap(), names);
+    assert_eq!(down(&conn, names.len()).await.unwrap().len(), names.len());
+    assert_eq!(up(&conn, None).await.unwrap(), names);
+}
+
+#[tokio::test]
+async fn a_failed_migration_is_rolled_back() {
+    let conn = database().await;
+    prepare(&conn).await.unwrap();
+    let statements = ["CREATE TABLE \"broken\" (\"id\" integer)", "NOT SQL"];
+    assert!(apply(&conn, "broken", &statements, history("broken"))
+        .await
+        .is_err());
+    assert!(applied(&conn).await.unwrap().is_empty());
+    assert!(execute(&conn, "check", &["SELECT * FROM \"broken\""])
+        .await
+        .is_err());
+}
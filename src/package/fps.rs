@@ -0,0 +1,130 @@
+use super::{cdata, escape, test_names, Package};
+use crate::judger::Kind;
+use anyhow::Result;
+use roxmltree::{Document, Node};
+use std::collections::BTreeMap;
+
+fn text(node: Node, name: &str) -> String {
+    node.children()
+        .find(|x| x.has_tag_name(name))
+        .map(|x| x.children().filter_map(|x| x.text()).collect::<String>())
+        .unwrap_or_default()
+}
+
+fn texts(node: Node, name: &str) -> Vec<String> {
+    node.children()
+        .filter(|x| x.has_tag_name(name))
+        .map(|x| x.children().filter_map(|x| x.text()).collect::<String>())
+        .collect()
+}
+
+fn unit(node: Node, name: &str) -> String {
+    node.children()
+        .find(|x| x.has_tag_name(name))
+        .and_then(|x| x.attribute("unit"))
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+pub fn import(data: &[u8]) -> Result<(Vec<Package>, Vec<String>)> {
+    let xml = std::str::from_utf8(data)?;
+    let document = Document::parse(xml)?;
+    let mut packages = vec![];
+    let mut warnings = vec![];
+    for item in document
+        .root_element()
+        .children()
+        .filter(|x| x.has_tag_name("item"))
+    {
+        let nick = text(item, "title").trim().to_string();
+        let time = text(item, "time_limit").trim().parse::<f64>()?;
+        let limit_time = match unit(item, "time_limit").as_str() {
+            "ms" => time,
+            _ => time * 1000.0,
+        } as u32;
+        let memory = text(item, "memory_limit").trim().parse::<f64>()?;
+        let limit_memory = match unit(item, "memory_limit").as_str() {
+            "kb" => memory * 1024.0,
+            _ => memory * 1024.0 * 1024.0,
+        } as u32;
+        let mut description = text(item, "description").trim().to_string();
+        for (section, title) in [("input", "Input"), ("output", "Output"), ("hint", "Hint")] {
+            let content = text(item, section);
+            if !content.trim().is_empty() {
+                description.push_str(&format!("\n\n<h2>{}</h2>\n\n{}", title, content.trim()));
+            }
+        }
+        let mut inputs = texts(item, "test_input");
+        let mut outputs = texts(item, "test_output");
+        if inputs.is_empty() {
+            inputs = texts(item, "sample_input");
+            outputs = texts(item, "sample_output");
+        }
+        if inputs.len() != outputs.len() {
+            warnings.push(format!("`{}` has unpaired tests", nick));
+        }
+        let width = inputs.len().to_string().len().max(2);
+        let mut files = BTreeMap::new();
+        for (i, (input, output)) in inputs.into_iter().zip(outputs).enumerate() {
+            files.insert(
+                format!("{:0width$}.in", i + 1, width = width),
+                input.into_bytes(),
+            );
+            files.insert(
+                format!("{:0width$}.out", i + 1, width = width),
+                output.into_bytes(),
+            );
+        }
+        if item.children().any(|x| x.has_tag_name("spj")) {
+            // hustoj special judges take their arguments in another order than testlib checkers
+            warnings.push(format!(
+                "`{}` has a special judge which is not imported",
+                nick
+            ));
+        }
+        packages.push(Package {
+            nick,
+            description,
+            limit_time,
+            limit_memory,
+            kind: Kind::Traditional,
+            input_file: None,
+            output_file: None,
+            files,
+        });
+    }
+    Ok((packages, warnings))
+}
+
+pub fn export(package: &Package) -> Result<Vec<u8>> {
+    let mut tests = String::new();
+    for name in test_names(&package.files) {
+        let input = String::from_utf8_lossy(&package.files[&format!("{}.in", name)]);
+        let output = String::from_utf8_lossy(&package.files[&format!("{}.out", name)]);
+        tests.push_str(&format!(
+            "    <test_input>{}</test_input>\n    <test_output>{}</test_output>\n",
+            cdata(&input),
+            cdata(&output)
+        ));
+    }
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<fps version="1.2" url="https://github.com/zhblue/freeproblemset/">
+  <generator name="{generator}" url=""/>
+  <item>
+    <title>{title}</title>
+    <time_limit unit="ms">{limit_time}</time_limit>
+    <memory_limit unit="mb">{limit_memory}</memory_limit>
+    <description>{description}</description>
+{tests}  </item>
+</fps>
+"#,
+        generator = escape(env!("CARGO_PKG_NAME")),
+        title = cdata(&package.nick),
+        limit_time = package.limit_time,
+        limit_memory = package.limit_memory >> 20,
+        description = cdata(&package.description),
+        tests = tests,
+    );
+    Ok(xml.into_bytes())
+}
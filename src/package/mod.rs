@@ -0,0 +1,143 @@
+pub mod fps;
+pub mod native;
+pub mod polygon;
+
+use crate::judger::Kind;
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read, Write};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    #[default]
+    Native,
+    Polygon,
+    Fps,
+}
+
+#[derive(Debug, Clone)]
+pub struct Package {
+    pub nick: String,
+    pub description: String,
+    pub limit_time: u32,
+    pub limit_memory: u32,
+    pub kind: Kind,
+    pub input_file: Option<String>,
+    pub output_file: Option<String>,
+    pub files: BTreeMap<String, Vec<u8>>,
+}
+
+pub fn import(format: Format, data: &[u8]) -> Result<(Vec<Package>, Vec<String>)> {
+    match format {
+        Format::Native => native::import(data),
+        Format::Polygon => polygon::import(data),
+        Format::Fps => fps::import(data),
+    }
+}
+
+pub fn export(format: Format, package: &Package) -> Result<Vec<u8>> {
+    match format {
+        Format::Native => native::export(package),
+        Format::Polygon => polygon::export(package),
+        Format::Fps => fps::export(package),
+    }
+}
+
+pub fn test_names(files: &BTreeMap<String, Vec<u8>>) -> Vec<String> {
+    files
+        .keys()
+        .filter_map(|x| x.strip_suffix(".in"))
+        .filter(|x| files.contains_key(&format!("{}.out", x)))
+        .map(|x| x.to_string())
+        .collect()
+}
+
+// bounds on what an uploaded archive unpacks to, so that a zip bomb cannot exhaust the memory
+pub const ARCHIVE_ENTRIES: usize = 4096;
+pub const ARCHIVE_SIZE: u64 = 1 << 30;
+
+// what is left of the bounds while an archive is unpacked
+pub struct Bound {
+    entries: usize,
+    size: u64,
+}
+
+impl Default for Bound {
+    fn default() -> Bound {
+        Bound::new(ARCHIVE_ENTRIES, ARCHIVE_SIZE)
+    }
+}
+
+impl Bound {
+    pub fn new(entries: usize, size: u64) -> Bound {
+        Bound { entries, size }
+    }
+
+    pub fn entry(&mut self) -> Result<()> {
+        if self.entries == 0 {
+            anyhow::bail!("the archive has too many entries");
+        }
+        self.entries -= 1;
+        Ok(())
+    }
+
+    pub fn read(&mut self, entry: impl Read) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        entry.take(self.size + 1).read_to_end(&mut data)?;
+        if data.len() as u64 > self.size {
+            anyhow::bail!("the archive unpacks to too many bytes");
+        }
+        self.size -= data.len() as u64;
+        Ok(data)
+    }
+}
+
+pub fn unzip(data: &[u8]) -> Result<BTreeMap<String, Vec<u8>>> {
+    unzip_bounded(data, &mut Bound::default())
+}
+
+pub fn unzip_bounded(data: &[u8], bound: &mut Bound) -> Result<BTreeMap<String, Vec<u8>>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))?;
+    let mut entries = BTreeMap::new();
+    for i in 0..archive.len() {
+        bound.entry()?;
+        let entry = archive.by_index(i)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = match entry.enclosed_name().and_then(|x| x.to_str()) {
+            Some(x) => x.replace('\\', "/"),
+            None => continue,
+        };
+        let data = bound.read(entry)?;
+        if entries.insert(name.clone(), data).is_some() {
+            anyhow::bail!("`{}` appears twice in the archive", name);
+        }
+    }
+    Ok(entries)
+}
+
+pub fn zip(entries: &BTreeMap<String, Vec<u8>>) -> Result<Vec<u8>> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (name, data) in entries {
+        writer.start_file(name, options)?;
+        writer.write_all(data)?;
+    }
+    Ok(writer.finish()?.into_inner())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn cdata(text: &str) -> String {
+    format!("<![CDATA[{}]]>", text.replace("]]>", "]]]]><![CDATA[>"))
+}
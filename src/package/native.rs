@@ -0,0 +1,71 @@
+use super::{unzip, zip, Package};
+use crate::judger::Kind;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Deserialize, Serialize)]
+pub struct Manifest {
+    pub nick: String,
+    pub description: String,
+    pub limit_time: u32,
+    pub limit_memory: u32,
+    #[serde(default)]
+    pub kind: Kind,
+    pub input_file: Option<String>,
+    pub output_file: Option<String>,
+}
+
+// an unpacked package, with `problem.json` beside the `testdata` directory
+pub fn manifest(dir: &Path) -> Result<Manifest> {
+    let path = dir.join("problem.json");
+    let data = std::fs::read(&path).with_context(|| format!("failed to read {:?}", path))?;
+    serde_json::from_slice(&data).with_context(|| format!("failed to parse {:?}", path))
+}
+
+pub fn import(data: &[u8]) -> Result<(Vec<Package>, Vec<String>)> {
+    let entries = unzip(data)?;
+    let manifest = entries
+        .get("problem.json")
+        .context("`problem.json` is missing")?;
+    let manifest: Manifest = serde_json::from_slice(manifest)?;
+    let mut files = BTreeMap::new();
+    for (name, data) in entries {
+        if let Some(name) = name.strip_prefix("testdata/") {
+            files.insert(name.to_string(), data);
+        }
+    }
+    let package = Package {
+        nick: manifest.nick,
+        description: manifest.description,
+        limit_time: manifest.limit_time,
+        limit_memory: manifest.limit_memory,
+        kind: manifest.kind,
+        input_file: manifest.input_file,
+        output_file: manifest.output_file,
+        files,
+    };
+    Ok((vec![package], vec![]))
+}
+
+pub fn export(package: &Package) -> Result<Vec<u8>> {
+    let manifest = Manifest {
+        nick: package.nick.clone(),
+        description: package.description.clone(),
+        limit_time: package.limit_time,
+        limit_memory: package.limit_memory,
+        kind: package.kind,
+        input_file: package.input_file.clone(),
+        output_file: package.output_file.clone(),
+    };
+    let mut entries = BTreeMap::new();
+    entries.insert(
+        "problem.json".to_string(),
+        serde_json::to_vec_pretty(&manifest)?,
+    );
+    for (name, data) in package.files.iter() {
+        entries.insert(format!("testdata/{}", name), data.clone());
+    }
+    zip(&entries)
+}
@@ -0,0 +1,204 @@
+use super::{escape, test_names, unzip, zip, Package};
+use crate::judger::Kind;
+use anyhow::{Context, Result};
+use roxmltree::{Document, Node};
+use std::collections::BTreeMap;
+
+fn child<'a, 'b>(node: Node<'a, 'b>, name: &str) -> Option<Node<'a, 'b>> {
+    node.children().find(|x| x.has_tag_name(name))
+}
+
+fn text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    child(node, name).and_then(|x| x.text()).map(|x| x.trim())
+}
+
+// expands the printf-style `%02d` used by polygon path patterns
+fn pattern(pattern: &str, index: usize) -> String {
+    let (head, tail) = match pattern.split_once('%') {
+        Some(x) => x,
+        None => return pattern.to_string(),
+    };
+    let (spec, tail) = match tail.split_once('d') {
+        Some(x) => x,
+        None => return pattern.to_string(),
+    };
+    let width = spec.parse::<usize>().unwrap_or(0);
+    format!("{}{:0width$}{}", head, index, tail, width = width)
+}
+
+pub fn import(data: &[u8]) -> Result<(Vec<Package>, Vec<String>)> {
+    let entries = unzip(data)?;
+    let xml = entries
+        .get("problem.xml")
+        .context("`problem.xml` is missing")?;
+    let xml = std::str::from_utf8(xml)?;
+    let document = Document::parse(xml)?;
+    let problem = document.root_element();
+    let mut warnings = vec![];
+    let names = child(problem, "names").context("`names` is missing")?;
+    let nick = names
+        .children()
+        .filter(|x| x.has_tag_name("name"))
+        .min_by_key(|x| x.attribute("language") != Some("english"))
+        .and_then(|x| x.attribute("value"))
+        .unwrap_or_default()
+        .to_string();
+    let judging = child(problem, "judging").context("`judging` is missing")?;
+    let input_file = judging
+        .attribute("input-file")
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_string());
+    let output_file = judging
+        .attribute("output-file")
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_string());
+    let testset = judging
+        .children()
+        .find(|x| x.has_tag_name("testset") && x.attribute("name") == Some("tests"))
+        .context("testset `tests` is missing")?;
+    let limit_time = text(testset, "time-limit").unwrap_or("1000").parse()?;
+    let limit_memory = text(testset, "memory-limit")
+        .unwrap_or("268435456")
+        .parse()?;
+    // the judger has no subtasks, every test is judged and scored on its own
+    let tests = child(testset, "tests")
+        .map(|x| {
+            x.children()
+                .filter(|x| x.has_tag_name("test"))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    if child(testset, "groups").is_some() || tests.iter().any(|x| x.has_attribute("group")) {
+        warnings
+            .push("test groups are not supported, they are imported as plain tests".to_string());
+    }
+    let count = match text(testset, "test-count") {
+        Some(x) => x.parse()?,
+        None => tests.len(),
+    };
+    let input_pattern = text(testset, "input-path-pattern").unwrap_or("tests/%02d");
+    let answer_pattern = text(testset, "answer-path-pattern").unwrap_or("tests/%02d.a");
+    let width = count.to_string().len().max(2);
+    let mut files = BTreeMap::new();
+    for i in 1..=count {
+        let input = entries.get(&pattern(input_pattern, i));
+        let answer = entries.get(&pattern(answer_pattern, i));
+        match (input, answer) {
+            (Some(input), Some(answer)) => {
+                files.insert(format!("{:0width$}.in", i, width = width), input.clone());
+                files.insert(format!("{:0width$}.out", i, width = width), answer.clone());
+            }
+            _ => warnings.push(format!(
+                "test {} is missing, export the package with generated tests",
+                i
+            )),
+        }
+    }
+    let mut kind = Kind::Traditional;
+    if let Some(assets) = child(problem, "assets") {
+        let source = |name: &str| {
+            child(assets, name)
+                .and_then(|x| child(x, "source"))
+                .and_then(|x| x.attribute("path"))
+                .and_then(|x| entries.get(x))
+        };
+        if let Some(checker) = source("checker") {
+            files.insert("checker.cpp".to_string(), checker.clone());
+        }
+        if let Some(interactor) = source("interactor") {
+            files.insert("interactor.cpp".to_string(), interactor.clone());
+            kind = Kind::Interactive;
+        }
+    }
+    if let Some(testlib) = entries.get("files/testlib.h") {
+        files.insert("testlib.h".to_string(), testlib.clone());
+    }
+    let mut description = String::new();
+    for (section, title) in [
+        ("legend", None),
+        ("input", Some("Input")),
+        ("output", Some("Output")),
+        ("notes", Some("Notes")),
+    ] {
+        let content = ["english", "russian", "chinese"]
+            .iter()
+            .find_map(|x| entries.get(&format!("statement-sections/{}/{}.tex", x, section)));
+        if let Some(content) = content {
+            if let Some(title) = title {
+                description.push_str(&format!("\n\n## {}\n\n", title));
+            }
+            description.push_str(String::from_utf8_lossy(content).trim());
+        }
+    }
+    let package = Package {
+        nick,
+        description: description.trim().to_string(),
+        limit_time,
+        limit_memory,
+        kind,
+        input_file,
+        output_file,
+        files,
+    };
+    Ok((vec![package], warnings))
+}
+
+pub fn export(package: &Package) -> Result<Vec<u8>> {
+    let tests = test_names(&package.files);
+    let mut entries = BTreeMap::new();
+    for (i, name) in tests.iter().enumerate() {
+        let input = &package.files[&format!("{}.in", name)];
+        let answer = &package.files[&format!("{}.out", name)];
+        entries.insert(format!("tests/{:02}", i + 1), input.clone());
+        entries.insert(format!("tests/{:02}.a", i + 1), answer.clone());
+    }
+    let mut assets = String::new();
+    if let Some(checker) = package.files.get("checker.cpp") {
+        entries.insert("files/check.cpp".to_string(), checker.clone());
+        assets.push_str(
+            "    <checker type=\"testlib\">\n      <source path=\"files/check.cpp\" type=\"cpp.g++17\"/>\n    </checker>\n",
+        );
+    }
+    if let Some(interactor) = package.files.get("interactor.cpp") {
+        entries.insert("files/interactor.cpp".to_string(), interactor.clone());
+        assets.push_str(
+            "    <interactor>\n      <source path=\"files/interactor.cpp\" type=\"cpp.g++17\"/>\n    </interactor>\n",
+        );
+    }
+    if let Some(testlib) = package.files.get("testlib.h") {
+        entries.insert("files/testlib.h".to_string(), testlib.clone());
+    }
+    entries.insert(
+        "statement-sections/english/legend.tex".to_string(),
+        package.description.as_bytes().to_vec(),
+    );
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="utf-8" standalone="no"?>
+<problem revision="1">
+  <names>
+    <name language="english" value="{nick}"/>
+  </names>
+  <judging input-file="{input_file}" output-file="{output_file}">
+    <testset name="tests">
+      <time-limit>{limit_time}</time-limit>
+      <memory-limit>{limit_memory}</memory-limit>
+      <test-count>{count}</test-count>
+      <input-path-pattern>tests/%02d</input-path-pattern>
+      <answer-path-pattern>tests/%02d.a</answer-path-pattern>
+    </testset>
+  </judging>
+  <assets>
+{assets}  </assets>
+</problem>
+"#,
+        nick = escape(&package.nick),
+        input_file = escape(package.input_file.as_deref().unwrap_or_default()),
+        output_file = escape(package.output_file.as_deref().unwrap_or_default()),
+        limit_time = package.limit_time,
+        limit_memory = package.limit_memory,
+        count = tests.len(),
+        assets = assets,
+    );
+    entries.insert("problem.xml".to_string(), xml.into_bytes());
+    zip(&entries)
+}
@@ -0,0 +1,182 @@
+pub mod token;
+
+#[cfg(test)]
+mod tests;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use uuid::Uuid;
+
+// any copied run of at least `K + W - 1` tokens is guaranteed to share a fingerprint
+pub const K: usize = 10;
+pub const W: usize = 6;
+
+pub struct Submission {
+    pub record_id: Uuid,
+    pub user_id: Uuid,
+    pub problem_id: Uuid,
+    pub language: String,
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Side {
+    pub record_id: Uuid,
+    pub user_id: Uuid,
+    pub similarity: f64,
+    pub lines: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Pair {
+    pub problem_id: Uuid,
+    pub language: String,
+    pub similarity: f64,
+    pub a: Side,
+    pub b: Side,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct Report {
+    pub submissions: usize,
+    pub pairs: Vec<Pair>,
+}
+
+struct Document {
+    lines: Vec<(usize, usize)>,
+    fingerprints: BTreeMap<u64, Vec<usize>>,
+    count: usize,
+}
+
+fn fnv(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |h, x| {
+        (h ^ x as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// keeps the rightmost minimal hash of every window
+pub fn winnow(hashes: &[u64]) -> Vec<(u64, usize)> {
+    let mut selected: Vec<(u64, usize)> = vec![];
+    if hashes.is_empty() {
+        return selected;
+    }
+    let w = W.min(hashes.len());
+    for i in 0..=hashes.len() - w {
+        let mut best = i;
+        for j in i..i + w {
+            if hashes[j] <= hashes[best] {
+                best = j;
+            }
+        }
+        if selected.last().map(|x| x.1) != Some(best) {
+            selected.push((hashes[best], best));
+        }
+    }
+    selected
+}
+
+fn document(language: &str, code: &str) -> Document {
+    let tokens = token::tokenize(language, code);
+    let starts = std::iter::once(0)
+        .chain(code.match_indices('\n').map(|(i, _)| i + 1))
+        .collect::<Vec<_>>();
+    let line = |offset: usize| starts.partition_point(|x| *x <= offset);
+    let lines = tokens
+        .iter()
+        .map(|x| (line(x.start), line(x.end - 1)))
+        .collect::<Vec<_>>();
+    let hashes = tokens.iter().map(|x| fnv(&x.text)).collect::<Vec<_>>();
+    let grams = if hashes.len() < K {
+        vec![]
+    } else {
+        (0..=hashes.len() - K)
+            .map(|i| {
+                hashes[i..i + K]
+                    .iter()
+                    .fold(0u64, |h, x| h.wrapping_mul(31).wrapping_add(*x))
+            })
+            .collect()
+    };
+    let mut fingerprints = BTreeMap::<u64, Vec<usize>>::new();
+    let selected = winnow(&grams);
+    for (hash, position) in selected.iter() {
+        fingerprints.entry(*hash).or_default().push(*position);
+    }
+    Document {
+        lines,
+        fingerprints,
+        count: selected.len(),
+    }
+}
+
+fn side(submission: &Submission, this: &Document, other: &Document) -> Side {
+    let mut shared = 0;
+    let mut covered = BTreeSet::new();
+    for (hash, positions) in this.fingerprints.iter() {
+        if other.fingerprints.contains_key(hash) {
+            shared += positions.len();
+            for position in positions {
+                covered.extend(*position..*position + K);
+            }
+        }
+    }
+    // consecutive tokens stay in one range across the comments and blank lines between them
+    let mut lines: Vec<(usize, usize)> = vec![];
+    let mut previous = None;
+    for i in covered {
+        let (start, end) = this.lines[i];
+        match lines.last_mut() {
+            Some(last) if previous.map(|x| x + 1) == Some(i) || start <= last.1 + 1 => {
+                last.1 = last.1.max(end)
+            }
+            _ => lines.push((start, end)),
+        }
+        previous = Some(i);
+    }
+    Side {
+        record_id: submission.record_id,
+        user_id: submission.user_id,
+        similarity: if this.count == 0 {
+            0.0
+        } else {
+            shared as f64 / this.count as f64
+        },
+        lines,
+    }
+}
+
+// compares submissions of the same problem and language by different users
+pub fn analyze(submissions: &[Submission], threshold: f64) -> Report {
+    let documents = submissions
+        .iter()
+        .map(|x| document(&x.language, &x.code))
+        .collect::<Vec<_>>();
+    let mut pairs = vec![];
+    for i in 0..submissions.len() {
+        for j in i + 1..submissions.len() {
+            let (x, y) = (&submissions[i], &submissions[j]);
+            if x.problem_id != y.problem_id || x.language != y.language || x.user_id == y.user_id {
+                continue;
+            }
+            let a = side(x, &documents[i], &documents[j]);
+            let b = side(y, &documents[j], &documents[i]);
+            let similarity = a.similarity.max(b.similarity);
+            if similarity < threshold || similarity == 0.0 {
+                continue;
+            }
+            pairs.push(Pair {
+                problem_id: x.problem_id,
+                language: x.language.clone(),
+                similarity,
+                a,
+                b,
+            });
+        }
+    }
+    pairs.sort_by(|x, y| y.similarity.total_cmp(&x.similarity));
+    Report {
+        submissions: submissions.len(),
+        pairs,
+    }
+}
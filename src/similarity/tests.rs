@@ -0,0 +1,151 @@
+use super::token::tokenize;
+use super::*;
+
+const SOURCE: &str = r#"#include <stdio.h>
+
+int gcd(int a, int b) {
+    // euclid
+    while (b != 0) {
+        int t = a % b;
+        a = b;
+        b = t;
+    }
+    return a;
+}
+
+int main() {
+    int n, m;
+    scanf("%d%d", &n, &m);
+    printf("%d\n", gcd(n, m));
+    return 0;
+}
+"#;
+
+const RENAMED: &str = r#"#include <stdio.h>
+#define MAX 100
+
+int f(int x, int y) {
+    while (y != 0) {
+        int r = x % y;
+        x = y;
+        y = r;
+    }
+    return x;
+}
+
+/* reads two numbers */
+int main() {
+    int p, q;
+    scanf("%d %d", &p, &q);
+    printf("%d\n", f(p, q));
+    return 0;
+}
+"#;
+
+const UNRELATED: &str = r#"#include <stdio.h>
+
+double sum[1000];
+
+void prefix(const double *values, unsigned count) {
+    for (unsigned i = 0; i < count; i++) sum[i + 1] = sum[i] + values[i];
+}
+
+struct point { double x, y; };
+
+static double cross(struct point p, struct point q) { return p.x * q.y - p.y * q.x; }
+"#;
+
+fn submission(user: u128, code: &str) -> Submission {
+    Submission {
+        record_id: Uuid::from_u128(user + 100),
+        user_id: Uuid::from_u128(user),
+        problem_id: Uuid::nil(),
+        language: "c".to_string(),
+        code: code.to_string(),
+    }
+}
+
+fn texts(code: &str) -> Vec<String> {
+    tokenize("c", code).into_iter().map(|x| x.text).collect()
+}
+
+#[test]
+fn tokenize_normalizes_identifiers_and_literals() {
+    assert_eq!(
+        texts("int x = 42; char *s = \"hi\";"),
+        ["int", "$id", "=", "$num", ";", "char", "*", "$id", "=", "$str", ";"]
+    );
+}
+
+#[test]
+fn tokenize_skips_comments_and_directives() {
+    assert_eq!(
+        texts("#include <a.h>\n#define X \\\n 1\n// x\n/* y */ return;"),
+        ["return", ";"]
+    );
+}
+
+#[test]
+fn tokenize_keeps_offsets() {
+    let code = "int main";
+    let tokens = tokenize("c", code);
+    assert_eq!(&code[tokens[1].start..tokens[1].end], "main");
+}
+
+#[test]
+fn renamed_identifiers_tokenize_the_same() {
+    assert_eq!(texts(SOURCE), texts(RENAMED));
+}
+
+#[test]
+fn winnow_selects_the_minimum_of_every_window() {
+    assert!(winnow(&[]).is_empty());
+    assert_eq!(winnow(&[3, 1, 2]), [(1, 1)]);
+    let hashes = [9, 8, 7, 6, 5, 4, 3, 2, 1, 0];
+    let selected = winnow(&hashes);
+    for i in 0..=hashes.len() - W {
+        let min = hashes[i..i + W].iter().min().unwrap();
+        assert!(selected
+            .iter()
+            .any(|x| x.0 == *min && (i..i + W).contains(&x.1)));
+    }
+}
+
+#[test]
+fn winnow_prefers_the_rightmost_minimum() {
+    assert_eq!(winnow(&[1, 1, 1]), [(1, 2)]);
+}
+
+#[test]
+fn identical_submissions_are_fully_similar() {
+    let report = analyze(&[submission(1, SOURCE), submission(2, SOURCE)], 0.5);
+    assert_eq!(report.submissions, 2);
+    assert_eq!(report.pairs.len(), 1);
+    let pair = &report.pairs[0];
+    assert_eq!(pair.similarity, 1.0);
+    assert_eq!(pair.a.lines, pair.b.lines);
+    // one range across the comment, from the function to the last fingerprint
+    assert_eq!(pair.a.lines, [(3, 16)]);
+}
+
+#[test]
+fn renamed_submissions_are_fully_similar() {
+    let report = analyze(&[submission(1, SOURCE), submission(2, RENAMED)], 0.5);
+    assert_eq!(report.pairs.len(), 1);
+    let pair = &report.pairs[0];
+    assert_eq!(pair.similarity, 1.0);
+    assert_eq!(pair.a.lines, [(3, 16)]);
+    assert_eq!(pair.b.lines, [(4, 17)]);
+}
+
+#[test]
+fn unrelated_submissions_are_not_reported() {
+    let report = analyze(&[submission(1, SOURCE), submission(2, UNRELATED)], 0.1);
+    assert!(report.pairs.is_empty());
+}
+
+#[test]
+fn submissions_of_one_user_are_not_compared() {
+    let report = analyze(&[submission(1, SOURCE), submission(1, SOURCE)], 0.0);
+    assert!(report.pairs.is_empty());
+}
@@ -0,0 +1,100 @@
+const C_KEYWORDS: &str = "auto break case char const continue default do double else enum \
+    extern float for goto if inline int long register restrict return short signed sizeof \
+    static struct switch typedef union unsigned void volatile while bool true false";
+
+const CXX_KEYWORDS: &str = "catch class constexpr decltype delete explicit friend mutable \
+    namespace new noexcept nullptr operator private protected public template this throw try \
+    typename using virtual";
+
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+fn is_keyword(language: &str, word: &str) -> bool {
+    let c = C_KEYWORDS.split_whitespace().any(|x| x == word);
+    match language {
+        "c" => c,
+        "cxx" => c || CXX_KEYWORDS.split_whitespace().any(|x| x == word),
+        _ => false,
+    }
+}
+
+// identifiers and literals are normalized, so renaming variables does not hide a copy
+pub fn tokenize(language: &str, code: &str) -> Vec<Token> {
+    let bytes = code.as_bytes();
+    let mut tokens = vec![];
+    let mut i = 0;
+    let mut line_start = true;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+        if c == b'\n' {
+            line_start = true;
+            i += 1;
+            continue;
+        }
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        if line_start && c == b'#' {
+            while i < bytes.len() && !(bytes[i] == b'\n' && bytes[i - 1] != b'\\') {
+                i += 1;
+            }
+            continue;
+        }
+        line_start = false;
+        if bytes[i..].starts_with(b"//") {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+            continue;
+        }
+        if bytes[i..].starts_with(b"/*") {
+            i += 2;
+            while i < bytes.len() && !bytes[i..].starts_with(b"*/") {
+                i += 1;
+            }
+            i = (i + 2).min(bytes.len());
+            continue;
+        }
+        let text = if c.is_ascii_alphabetic() || c == b'_' {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            let word = &code[start..i];
+            if is_keyword(language, word) {
+                word.to_string()
+            } else {
+                "$id".to_string()
+            }
+        } else if c.is_ascii_digit() {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.') {
+                i += 1;
+            }
+            "$num".to_string()
+        } else if c == b'"' || c == b'\'' {
+            i += 1;
+            while i < bytes.len() && bytes[i] != c && bytes[i] != b'\n' {
+                if bytes[i] == b'\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            i = (i + 1).min(bytes.len());
+            "$str".to_string()
+        } else {
+            i += code[i..].chars().next().map(|x| x.len_utf8()).unwrap_or(1);
+            code[start..i].to_string()
+        };
+        tokens.push(Token {
+            text,
+            start,
+            end: i,
+        });
+    }
+    tokens
+}
@@ -1,8 +1,9 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.8.0
 
 use sea_orm::entity::prelude::*;
+use serde::Serialize;
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
 #[sea_orm(table_name = "contest")]
 pub struct Model {
     #[sea_orm(
@@ -16,6 +17,7 @@ pub struct Model {
     pub description: String,
     pub start: DateTime,
     pub end: DateTime,
+    pub freeze_time: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -24,6 +26,8 @@ pub enum Relation {
     Privilege,
     #[sea_orm(has_many = "super::problem::Entity")]
     Problem,
+    #[sea_orm(has_many = "super::similarity::Entity")]
+    Similarity,
 }
 
 impl Related<super::privilege::Entity> for Entity {
@@ -38,4 +42,10 @@ impl Related<super::problem::Entity> for Entity {
     }
 }
 
+impl Related<super::similarity::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Similarity.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
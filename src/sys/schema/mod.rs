@@ -2,8 +2,11 @@
 
 pub mod prelude;
 
+pub mod audit_log;
 pub mod contest;
 pub mod privilege;
 pub mod problem;
 pub mod record;
+pub mod similarity;
+pub mod token;
 pub mod user;
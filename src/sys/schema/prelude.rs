@@ -1,7 +1,10 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.8.0
 
+pub use super::audit_log::Entity as AuditLog;
 pub use super::contest::Entity as Contest;
 pub use super::privilege::Entity as Privilege;
 pub use super::problem::Entity as Problem;
 pub use super::record::Entity as Record;
+pub use super::similarity::Entity as Similarity;
+pub use super::token::Entity as Token;
 pub use super::user::Entity as User;
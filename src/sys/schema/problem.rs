@@ -1,8 +1,9 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.8.0
 
 use sea_orm::entity::prelude::*;
+use serde::Serialize;
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
 #[sea_orm(table_name = "problem")]
 pub struct Model {
     #[sea_orm(
@@ -14,10 +15,14 @@ pub struct Model {
     pub nick: String,
     #[sea_orm(column_type = "Text")]
     pub description: String,
-    pub limit_time: u32,
-    pub limit_memory: u32,
+    pub limit_time: i64,
+    pub limit_memory: i64,
     #[sea_orm(column_type = "Custom(\"uuid\".to_owned())")]
     pub contest_id: String,
+    pub kind: String,
+    pub input_file: Option<String>,
+    pub output_file: Option<String>,
+    pub testdata: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
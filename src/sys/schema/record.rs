@@ -22,6 +22,7 @@ pub struct Model {
     #[sea_orm(column_type = "Custom(\"LONGTEXT\".to_owned())")]
     pub result: String,
     pub status: String,
+    pub testdata: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,8 +1,9 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.8.0
 
 use sea_orm::entity::prelude::*;
+use serde::Serialize;
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
 #[sea_orm(table_name = "user")]
 pub struct Model {
     #[sea_orm(
@@ -16,9 +17,10 @@ pub struct Model {
     pub nick: String,
     #[sea_orm(column_type = "Text")]
     pub description: String,
+    #[serde(skip_serializing)]
     pub password: Vec<u8>,
-    pub banned: i8,
-    pub root: i8,
+    pub banned: bool,
+    pub root: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -27,6 +29,8 @@ pub enum Relation {
     Privilege,
     #[sea_orm(has_many = "super::record::Entity")]
     Record,
+    #[sea_orm(has_many = "super::token::Entity")]
+    Token,
 }
 
 impl Related<super::privilege::Entity> for Entity {
@@ -41,4 +45,10 @@ impl Related<super::record::Entity> for Entity {
     }
 }
 
+impl Related<super::token::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Token.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -7,6 +7,10 @@ pub fn sha256(text: &impl AsRef<[u8]>) -> Vec<u8> {
     hasher.finalize().to_vec()
 }
 
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
 #[macro_export]
 macro_rules! uuid {
     ($id: expr) => {
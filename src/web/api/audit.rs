@@ -0,0 +1,115 @@
+use super::auth::Principal;
+use crate::sys::schema::*;
+use crate::web::list::ListQuery;
+use crate::web::prelude::*;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use chrono::Utc;
+use schemars::JsonSchema;
+use sea_orm::prelude::*;
+use sea_orm::{ConnectionTrait, Set};
+use serde::Serialize;
+use serde_json::Value;
+use std::str::FromStr;
+use uuid::Uuid;
+
+// updates only keep the fields that changed
+fn diff(before: Option<Value>, after: Option<Value>) -> (Option<Value>, Option<Value>) {
+    match (before, after) {
+        (Some(Value::Object(mut before)), Some(Value::Object(mut after))) => {
+            let same: Vec<String> = before
+                .iter()
+                .filter(|(k, v)| after.get(*k) == Some(*v))
+                .map(|(k, _)| k.clone())
+                .collect();
+            for k in same {
+                before.remove(&k);
+                after.remove(&k);
+            }
+            (Some(Value::Object(before)), Some(Value::Object(after)))
+        }
+        x => x,
+    }
+}
+
+pub async fn log<C: ConnectionTrait, T: Serialize>(
+    conn: &C,
+    principal: &Principal,
+    action: &str,
+    target: Uuid,
+    before: Option<&T>,
+    after: Option<&T>,
+) -> AppResult<()> {
+    let before = before.map(|x| serde_json::to_value(x).unwrap());
+    let after = after.map(|x| serde_json::to_value(x).unwrap());
+    let (before, after) = diff(before, after);
+    audit_log::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        time: Set(Utc::now().naive_local()),
+        user_id: Set(principal.id().to_string()),
+        action: Set(action.to_string()),
+        target: Set(target.to_string()),
+        before: Set(before.map(|x| x.to_string())),
+        after: Set(after.map(|x| x.to_string())),
+    }
+    .insert(conn)
+    .await?;
+    Ok(())
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct AuditModel {
+    pub id: Uuid,
+    pub time: DateTime,
+    pub user_id: Uuid,
+    pub action: String,
+    pub target: Uuid,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct AuditListFeedback {
+    pub entries: Vec<AuditModel>,
+    pub total: u64,
+}
+
+pub async fn audit_list(
+    list: ListQuery,
+    Extension(ref conn): Extension<DatabaseConnection>,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    principal.check_root()?;
+    let mut select = audit_log::Entity::find();
+    if let Some(user_id) = list.user_id {
+        select = select.filter(audit_log::Column::UserId.eq(user_id.to_string()));
+    }
+    if let Some(ref action) = list.action {
+        select = select.filter(audit_log::Column::Action.eq(action.clone()));
+    }
+    if let Some(target) = list.target {
+        select = select.filter(audit_log::Column::Target.eq(target.to_string()));
+    }
+    let select = list.range(select, audit_log::Column::Time);
+    let select = list.sort(
+        select,
+        &[
+            ("time", audit_log::Column::Time),
+            ("action", audit_log::Column::Action),
+        ],
+    )?;
+    let (models, total) = list.fetch(select, conn).await?;
+    let entries = models
+        .into_iter()
+        .map(|x| AuditModel {
+            id: uuid!(x.id),
+            time: x.time,
+            user_id: uuid!(x.user_id),
+            action: x.action,
+            target: uuid!(x.target),
+            before: x.before.and_then(|x| serde_json::from_str(&x).ok()),
+            after: x.after.and_then(|x| serde_json::from_str(&x).ok()),
+        })
+        .collect();
+    Ok(Json(AuditListFeedback { entries, total }))
+}
@@ -1,27 +1,78 @@
 use crate::sys::schema::*;
-use crate::utils::sha256;
+use crate::utils::{hex, sha256};
 use crate::web::prelude::*;
+use axum::async_trait;
+use axum::extract::{FromRequest, RequestParts};
+use axum::http::header::AUTHORIZATION;
+use axum::http::Method;
 use axum::response::IntoResponse;
 use axum::{Extension, Json};
+use chrono::Utc;
+use schemars::JsonSchema;
 use sea_orm::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 use tower_cookies::{Cookie, Cookies};
 use uuid::Uuid;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct Session {
     pub id: Uuid,
     pub password: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    ReadOnly,
+    Submit,
+    ContestAdmin,
+}
+
+impl From<Scope> for &'static str {
+    fn from(s: Scope) -> Self {
+        match s {
+            Scope::ReadOnly => "read_only",
+            Scope::Submit => "submit",
+            Scope::ContestAdmin => "contest_admin",
+        }
+    }
+}
+
+impl FromStr for Scope {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "read_only" => Ok(Scope::ReadOnly),
+            "submit" => Ok(Scope::Submit),
+            "contest_admin" => Ok(Scope::ContestAdmin),
+            _ => Err(anyhow::anyhow!("`{}` is not a token scope", s)),
+        }
+    }
+}
+
+impl Scope {
+    pub fn allows(self, method: &Method, path: &str) -> bool {
+        if method == Method::GET || method == Method::HEAD {
+            return true;
+        }
+        match self {
+            Scope::ReadOnly => false,
+            Scope::Submit => path == "/api/submit",
+            Scope::ContestAdmin => {
+                !path.starts_with("/api/user") && !path.starts_with("/api/token")
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
 pub struct LoginPayload {
     pub name: String,
     pub password: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(tag = "type")]
 pub enum LoginFeedback {
     Ok {},
@@ -33,7 +84,11 @@ pub async fn auth_login(
     Extension(ref conn): Extension<DatabaseConnection>,
     cookies: Cookies,
 ) -> AppResult<impl IntoResponse> {
-    let model = user::Entity::find().one(conn).await?.found()?;
+    let model = user::Entity::find()
+        .filter(user::Column::Name.eq(payload.name))
+        .one(conn)
+        .await?
+        .found()?;
     let id = model.id;
     let expected = model.password;
     if sha256(&payload.password) != expected {
@@ -41,7 +96,7 @@ pub async fn auth_login(
             message: "incorrect password".to_string(),
         }));
     }
-    if model.banned == 0 {
+    if model.banned {
         return Ok(Json(LoginFeedback::Err {
             message: "account is banned".to_string(),
         }));
@@ -61,208 +116,263 @@ pub async fn auth_logout(cookies: Cookies) -> impl IntoResponse {
     cookies.remove(Cookie::named("session"));
 }
 
-pub async fn test_user(conn: &DatabaseConnection, cookies: Cookies) -> Result<Uuid, ()> {
-    let session: Session =
-        serde_json::from_str(cookies.get("session").ok_or(())?.value()).map_err(|_| ())?;
-    let model = user::Entity::find_by_id(session.id.to_string())
-        .one(conn)
-        .await
-        .map_err(|_| ())?
-        .ok_or(())?;
-    if sha256(&session.password) != model.password {
-        return Err(());
-    }
-    if model.banned == 0 {
-        return Err(());
-    }
-    if model.root != 0 {
-        return Ok(session.id);
-    }
-    Ok(session.id)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Root,
+    Admin,
+    ProblemSetter,
+    Judge,
+    Observer,
+    BalloonRunner,
+    Player,
 }
 
-pub async fn check_user(conn: &DatabaseConnection, cookies: &Cookies) -> AppResult<Uuid> {
-    let session: Session = serde_json::from_str(cookies.get("session").allow()?.value())
-        .ok()
-        .allow()?;
-    let model = user::Entity::find_by_id(session.id.to_string())
-        .one(conn)
-        .await?
-        .allow()?;
-    if sha256(&session.password) != model.password {
-        return Err(AppError::Forbidden(None));
-    }
-    if model.banned == 0 {
-        return Err(AppError::Forbidden(None));
-    }
-    if model.root != 0 {
-        return Ok(session.id);
-    }
-    Ok(session.id)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    View,
+    Ranklist,
+    Records,
+    Submit,
+    Edit,
+    Rejudge,
+    Similarity,
+    Manage,
 }
 
-pub async fn test_root(conn: &DatabaseConnection, cookies: &Cookies) -> Result<Uuid, ()> {
-    let session: Session =
-        serde_json::from_str(cookies.get("session").ok_or(())?.value()).map_err(|_| ())?;
-    let model = user::Entity::find_by_id(session.id.to_string())
-        .one(conn)
-        .await
-        .map_err(|_| ())?
-        .ok_or(())?;
-    if sha256(&session.password) != model.password {
-        return Err(());
-    }
-    if model.banned == 0 {
-        return Err(());
-    }
-    if model.root != 0 {
-        return Ok(session.id);
+const PERMISSIONS: &[(Role, &[Action])] = &[
+    (
+        Role::Root,
+        &[
+            Action::View,
+            Action::Ranklist,
+            Action::Records,
+            Action::Submit,
+            Action::Edit,
+            Action::Rejudge,
+            Action::Similarity,
+            Action::Manage,
+        ],
+    ),
+    (
+        Role::Admin,
+        &[
+            Action::View,
+            Action::Ranklist,
+            Action::Records,
+            Action::Submit,
+            Action::Edit,
+            Action::Rejudge,
+            Action::Similarity,
+        ],
+    ),
+    (Role::ProblemSetter, &[Action::View, Action::Edit]),
+    (
+        Role::Judge,
+        &[
+            Action::View,
+            Action::Ranklist,
+            Action::Records,
+            Action::Rejudge,
+        ],
+    ),
+    (
+        Role::Observer,
+        &[Action::View, Action::Ranklist, Action::Records],
+    ),
+    (Role::BalloonRunner, &[Action::View, Action::Ranklist]),
+    (
+        Role::Player,
+        &[Action::View, Action::Ranklist, Action::Submit],
+    ),
+];
+
+impl Role {
+    pub fn permits(self, action: Action) -> bool {
+        PERMISSIONS
+            .iter()
+            .any(|(role, actions)| *role == self && actions.contains(&action))
     }
-    Ok(session.id)
 }
 
-pub async fn check_root(conn: &DatabaseConnection, cookies: &Cookies) -> AppResult<Uuid> {
-    let session: Session = serde_json::from_str(cookies.get("session").allow()?.value())
-        .ok()
-        .allow()?;
-    let model = user::Entity::find_by_id(session.id.to_string())
-        .one(conn)
-        .await?
-        .allow()?;
-    if sha256(&session.password) != model.password {
-        return Err(AppError::Forbidden(None));
-    }
-    if model.banned == 0 {
-        return Err(AppError::Forbidden(None));
-    }
-    if model.root != 0 {
-        return Ok(session.id);
-    }
-    Err(AppError::Forbidden(None))
+pub fn permits(roles: &[Role], action: Action) -> bool {
+    roles.iter().any(|x| x.permits(action))
 }
 
-pub async fn check_root_or_admin_of_contest(
-    contest_id: Uuid,
-    conn: &DatabaseConnection,
-    cookies: &Cookies,
-) -> AppResult<Uuid> {
-    let session: Session = serde_json::from_str(cookies.get("session").allow()?.value())
-        .ok()
-        .allow()?;
-    let model = user::Entity::find_by_id(session.id.to_string())
-        .one(conn)
-        .await?
-        .allow()?;
-    if sha256(&session.password) != model.password {
-        return Err(AppError::Forbidden(None));
-    }
-    if model.banned == 0 {
-        return Err(AppError::Forbidden(None));
-    }
-    if model.root != 0 {
-        return Ok(session.id);
-    }
-    privilege::Entity::find()
-        .filter(privilege::Column::ContestId.like(&contest_id.to_string()))
-        .filter(privilege::Column::UserId.like(&model.id))
-        .filter(privilege::Column::Kind.like("admin"))
-        .one(conn)
-        .await?
-        .allow()?;
-    Ok(session.id)
+// observers see records only up to the freeze, unless another role shows them all
+pub fn frozen(roles: &[Role]) -> bool {
+    !roles
+        .iter()
+        .any(|x| *x != Role::Observer && x.permits(Action::Records))
 }
 
-pub async fn check_root_or_player_of_contest(
-    contest_id: Uuid,
-    conn: &DatabaseConnection,
-    cookies: &Cookies,
-) -> AppResult<Uuid> {
-    let session: Session = serde_json::from_str(cookies.get("session").allow()?.value())
-        .ok()
-        .allow()?;
-    let model = user::Entity::find_by_id(session.id.to_string())
-        .one(conn)
-        .await?
-        .allow()?;
-    if sha256(&session.password) != model.password {
-        return Err(AppError::Forbidden(None));
-    }
-    if model.banned == 0 {
-        return Err(AppError::Forbidden(None));
+// the privilege kinds that grant an action
+pub fn kinds(action: Action) -> Vec<&'static str> {
+    PERMISSIONS
+        .iter()
+        .filter(|(role, actions)| *role != Role::Root && actions.contains(&action))
+        .map(|(role, _)| (*role).into())
+        .collect()
+}
+
+impl From<Role> for &'static str {
+    fn from(r: Role) -> Self {
+        match r {
+            Role::Root => "root",
+            Role::Admin => "admin",
+            Role::ProblemSetter => "problem_setter",
+            Role::Judge => "judge",
+            Role::Observer => "observer",
+            Role::BalloonRunner => "balloon_runner",
+            Role::Player => "player",
+        }
     }
-    if model.root != 0 {
-        return Ok(session.id);
+}
+
+// root is granted by the user table, never by a privilege
+impl FromStr for Role {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "admin" => Ok(Role::Admin),
+            "problem_setter" => Ok(Role::ProblemSetter),
+            "judge" => Ok(Role::Judge),
+            "observer" => Ok(Role::Observer),
+            "balloon_runner" => Ok(Role::BalloonRunner),
+            "player" => Ok(Role::Player),
+            _ => Err(anyhow::anyhow!("`{}` is not a contest role", s)),
+        }
     }
-    privilege::Entity::find()
-        .filter(privilege::Column::ContestId.like(&contest_id.to_string()))
-        .filter(privilege::Column::UserId.like(&model.id))
-        .filter(privilege::Column::Kind.like("player"))
-        .one(conn)
-        .await?
-        .allow()?;
-    Ok(session.id)
 }
 
-pub async fn test_root_or_admin_or_player_of_contest(
-    contest_id: Uuid,
-    conn: &DatabaseConnection,
-    cookies: &Cookies,
-) -> AppResult<Result<Uuid, Result<Uuid, Uuid>>> {
-    let session: Session = serde_json::from_str(cookies.get("session").allow()?.value())
-        .ok()
-        .allow()?;
-    let model = user::Entity::find_by_id(session.id.to_string())
-        .one(conn)
-        .await?
-        .allow()?;
-    if sha256(&session.password) != model.password {
-        return Err(AppError::Forbidden(None));
+// the signed-in user, from the session cookie or an api token sent as `Authorization: Bearer`
+pub struct Principal {
+    pub user: user::Model,
+    pub scope: Option<Scope>,
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for Principal {
+    type Rejection = AppError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Extension(conn) = Extension::<DatabaseConnection>::from_request(req)
+            .await
+            .map_err(|_| AppError::Forbidden(None))?;
+        let token = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.strip_prefix("Bearer "))
+            .map(|x| x.trim().to_string());
+        let (mut user, scope) = match token {
+            Some(token) => {
+                let model = token::Entity::find()
+                    .filter(token::Column::Hash.eq(hex(&sha256(&token))))
+                    .one(&conn)
+                    .await?
+                    .allow()?;
+                if let Some(expires) = model.expires {
+                    if expires <= Utc::now().naive_local() {
+                        return Err(AppError::Forbidden(Some(anyhow::anyhow!(
+                            "token has expired"
+                        ))));
+                    }
+                }
+                let scope = Scope::from_str(&model.scope).allow()?;
+                if !scope.allows(req.method(), req.uri().path()) {
+                    return Err(AppError::Forbidden(Some(anyhow::anyhow!(
+                        "token scope `{}` does not allow this request",
+                        model.scope
+                    ))));
+                }
+                let user = user::Entity::find_by_id(model.user_id)
+                    .one(&conn)
+                    .await?
+                    .allow()?;
+                (user, Some(scope))
+            }
+            None => {
+                let cookies = Cookies::from_request(req)
+                    .await
+                    .map_err(|_| AppError::Forbidden(None))?;
+                let session: Session =
+                    serde_json::from_str(cookies.get("session").allow()?.value())
+                        .ok()
+                        .allow()?;
+                let user = user::Entity::find_by_id(session.id.to_string())
+                    .one(&conn)
+                    .await?
+                    .allow()?;
+                if sha256(&session.password) != user.password {
+                    return Err(AppError::Forbidden(None));
+                }
+                (user, None)
+            }
+        };
+        if user.banned {
+            return Err(AppError::Forbidden(None));
+        }
+        // tokens never carry root, so a leaked token cannot manage the instance
+        if scope.is_some() {
+            user.root = false;
+        }
+        Ok(Principal { user, scope })
     }
-    if model.banned == 0 {
-        return Err(AppError::Forbidden(None));
+}
+
+impl Principal {
+    pub fn id(&self) -> Uuid {
+        uuid!(self.user.id)
     }
-    if model.root != 0 {
-        return Ok(Ok(session.id));
+
+    pub fn is_root(&self) -> bool {
+        self.user.root
     }
-    let x = privilege::Entity::find()
-        .filter(privilege::Column::ContestId.like(&contest_id.to_string()))
-        .filter(privilege::Column::UserId.like(&model.id))
-        .one(conn)
-        .await?
-        .allow()?;
-    if x.kind == "admin" {
-        Ok(Err(Ok(session.id)))
-    } else {
-        Ok(Err(Err(session.id)))
+
+    pub fn check_session(&self) -> AppResult<Uuid> {
+        if self.scope.is_some() {
+            return Err(AppError::Forbidden(Some(anyhow::anyhow!(
+                "tokens cannot be used here, sign in instead"
+            ))));
+        }
+        Ok(self.id())
     }
-}
 
-pub async fn check_root_or_admin_or_player_of_contest(
-    contest_id: Uuid,
-    conn: &DatabaseConnection,
-    cookies: &Cookies,
-) -> AppResult<Uuid> {
-    let session: Session = serde_json::from_str(cookies.get("session").allow()?.value())
-        .ok()
-        .allow()?;
-    let model = user::Entity::find_by_id(session.id.to_string())
-        .one(conn)
-        .await?
-        .allow()?;
-    if sha256(&session.password) != model.password {
-        return Err(AppError::Forbidden(None));
+    pub fn check_root(&self) -> AppResult<Uuid> {
+        if self.is_root() {
+            Ok(self.id())
+        } else {
+            Err(AppError::Forbidden(None))
+        }
     }
-    if model.banned == 0 {
-        return Err(AppError::Forbidden(None));
+
+    pub async fn roles(&self, conn: &DatabaseConnection, contest_id: Uuid) -> AppResult<Vec<Role>> {
+        if self.is_root() {
+            return Ok(vec![Role::Root]);
+        }
+        let roles = privilege::Entity::find()
+            .filter(privilege::Column::ContestId.eq(contest_id.to_string()))
+            .filter(privilege::Column::UserId.eq(self.user.id.clone()))
+            .all(conn)
+            .await?
+            .into_iter()
+            .filter_map(|x| Role::from_str(&x.kind).ok())
+            .collect();
+        Ok(roles)
     }
-    if model.root != 0 {
-        return Ok(session.id);
+
+    pub async fn check(
+        &self,
+        conn: &DatabaseConnection,
+        contest_id: Uuid,
+        action: Action,
+    ) -> AppResult<Vec<Role>> {
+        let roles = self.roles(conn, contest_id).await?;
+        if permits(&roles, action) {
+            Ok(roles)
+        } else {
+            Err(AppError::Forbidden(None))
+        }
     }
-    privilege::Entity::find()
-        .filter(privilege::Column::ContestId.like(&contest_id.to_string()))
-        .filter(privilege::Column::UserId.like(&model.id))
-        .one(conn)
-        .await?
-        .allow()?;
-    Ok(session.id)
 }
@@ -1,25 +1,38 @@
-use super::auth;
+use super::audit;
+use super::auth::{Action, Principal, Role};
+use super::problem::check_file_names;
+use super::submit::check_output_name;
+use super::testdata::{commit, prepare, replace};
+use crate::judger::version::{history_dir, restore, snapshot};
+use crate::judger::{output_dir, testdata_dir, Kind};
+use crate::package::{self, Bound};
 use crate::sys::schema::*;
+use crate::web::list::ListQuery;
+use crate::web::params::Params;
 use crate::web::prelude::*;
+use axum::extract::Multipart;
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
 use axum::response::IntoResponse;
 use axum::{Extension, Json};
+use schemars::JsonSchema;
 use sea_orm::prelude::*;
-use sea_orm::{JoinType, QueryOrder, QuerySelect, Set};
+use sea_orm::sea_query::Query;
+use sea_orm::{JoinType, QueryOrder, QuerySelect, Set, TransactionTrait};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::str::FromStr;
-use tower_cookies::Cookies;
 use uuid::Uuid;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct ContestCreatePayload {
     pub nick: String,
     pub description: String,
     pub start: DateTime,
     pub end: DateTime,
+    pub freeze_time: Option<DateTime>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ContestCreateFeedback {
     pub id: Uuid,
 }
@@ -27,42 +40,59 @@ pub struct ContestCreateFeedback {
 pub async fn contest_create(
     Json(payload): Json<ContestCreatePayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    auth::check_root(conn, &cookies).await?;
+    principal.check_root()?;
+    let txn = conn.begin().await?;
     let model = contest::ActiveModel {
         id: Set(Uuid::new_v4().to_string()),
         nick: Set(payload.nick),
         description: Set(payload.description),
         start: Set(payload.start),
         end: Set(payload.end),
+        freeze_time: Set(payload.freeze_time),
     }
-    .insert(conn)
+    .insert(&txn)
     .await?;
+    audit::log(
+        &txn,
+        &principal,
+        "contest_create",
+        uuid!(model.id),
+        None,
+        Some(&model),
+    )
+    .await?;
+    txn.commit().await?;
     Ok(Json(ContestCreateFeedback {
         id: uuid!(model.id),
     }))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct ContestUpdatePayload {
     pub id: Uuid,
     pub nick: Option<String>,
     pub description: Option<String>,
     pub start: Option<DateTime>,
     pub end: Option<DateTime>,
+    pub freeze_time: Option<DateTime>,
+    #[serde(default)]
+    #[schemars(description = "Clears the freeze time, observers see every record again")]
+    pub unfreeze: bool,
 }
 
 pub async fn contest_update(
     Json(payload): Json<ContestUpdatePayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    auth::check_root(conn, &cookies).await?;
+    principal.check_root()?;
     let model = contest::Entity::find_by_id(payload.id.to_string())
         .one(conn)
         .await?
         .found()?;
+    let before = model.clone();
     let mut model: contest::ActiveModel = model.into();
     if let Some(nick) = payload.nick {
         model.nick = Set(nick);
@@ -76,29 +106,47 @@ pub async fn contest_update(
     if let Some(end) = payload.end {
         model.end = Set(end);
     }
-    model.update(conn).await?;
+    if let Some(freeze_time) = payload.freeze_time {
+        model.freeze_time = Set(Some(freeze_time));
+    }
+    if payload.unfreeze {
+        model.freeze_time = Set(None);
+    }
+    let txn = conn.begin().await?;
+    let model = model.update(&txn).await?;
+    audit::log(
+        &txn,
+        &principal,
+        "contest_update",
+        payload.id,
+        Some(&before),
+        Some(&model),
+    )
+    .await?;
+    txn.commit().await?;
     Ok(Json(()))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct ContestReadPayload {
     pub id: Uuid,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ContestReadFeedback {
     pub nick: String,
     pub description: String,
     pub start: DateTime,
     pub end: DateTime,
+    pub freeze_time: Option<DateTime>,
 }
 
 pub async fn contest_read(
-    Json(payload): Json<ContestReadPayload>,
+    Params(payload): Params<ContestReadPayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    auth::check_root_or_admin_or_player_of_contest(payload.id, conn, &cookies).await?;
+    principal.check(conn, payload.id, Action::View).await?;
     let model = contest::Entity::find_by_id(payload.id.to_string())
         .one(conn)
         .await?
@@ -108,10 +156,11 @@ pub async fn contest_read(
         description: model.description,
         start: model.start,
         end: model.end,
+        freeze_time: model.freeze_time,
     }))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct ContestDeletePayload {
     pub id: Uuid,
 }
@@ -119,63 +168,73 @@ pub struct ContestDeletePayload {
 pub async fn contest_delete(
     Json(payload): Json<ContestDeletePayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    auth::check_root(conn, &cookies).await?;
-    contest::Entity::delete_by_id(payload.id.to_string())
-        .exec(conn)
-        .await?;
+    principal.check_root()?;
+    let model = contest::Entity::find_by_id(payload.id.to_string())
+        .one(conn)
+        .await?
+        .found()?;
+    let txn = conn.begin().await?;
+    model.clone().delete(&txn).await?;
+    audit::log(
+        &txn,
+        &principal,
+        "contest_delete",
+        payload.id,
+        Some(&model),
+        None,
+    )
+    .await?;
+    txn.commit().await?;
     Ok(Json(()))
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ContestModel {
     pub id: Uuid,
     pub nick: String,
     pub description: String,
     pub start: DateTime,
     pub end: DateTime,
+    pub freeze_time: Option<DateTime>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ContestListFeedback {
     contests: Vec<ContestModel>,
+    total: u64,
 }
 
 pub async fn contest_list(
+    list: ListQuery,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    let user_id = auth::check_user(conn, &cookies).await?;
-    if auth::test_root(conn, &cookies).await.is_ok() {
-        let contests = contest::Entity::find()
-            .order_by_asc(contest::Column::Start)
-            .all(conn)
-            .await?
-            .into_iter()
-            .map(
-                |contest::Model {
-                     id,
-                     nick,
-                     description,
-                     start,
-                     end,
-                 }| ContestModel {
-                    id: uuid!(id),
-                    nick,
-                    description,
-                    start,
-                    end,
-                },
-            )
-            .collect();
-        return Ok(Json(ContestListFeedback { contests }));
-    }
-    let contests = contest::Entity::find()
-        .inner_join(privilege::Entity)
-        .filter(privilege::Column::UserId.eq(user_id.to_string()))
-        .all(conn)
-        .await?
+    let user_id = principal.id();
+    let mut select = contest::Entity::find();
+    if !principal.is_root() {
+        select = select.filter(
+            contest::Column::Id.in_subquery(
+                Query::select()
+                    .column(privilege::Column::ContestId)
+                    .from(privilege::Entity)
+                    .and_where(privilege::Column::UserId.eq(user_id.to_string()))
+                    .to_owned(),
+            ),
+        );
+    }
+    let select = list.range(select, contest::Column::Start);
+    let select = list.sort(
+        select,
+        &[
+            ("start", contest::Column::Start),
+            ("end", contest::Column::End),
+            ("nick", contest::Column::Nick),
+        ],
+    )?;
+    let (models, total) = list.fetch(select, conn).await?;
+    let contests = models
         .into_iter()
         .map(
             |contest::Model {
@@ -184,38 +243,40 @@ pub async fn contest_list(
                  description,
                  start,
                  end,
+                 freeze_time,
              }| ContestModel {
                 id: uuid!(id),
                 nick,
                 description,
                 start,
                 end,
+                freeze_time,
             },
         )
         .collect();
-    Ok(Json(ContestListFeedback { contests }))
+    Ok(Json(ContestListFeedback { contests, total }))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct ContestRanklistPayload {
     pub id: Uuid,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ContestRanklistFeedback {
     pub records: BTreeMap<Uuid, BTreeMap<Uuid, (Uuid, String, DateTime)>>,
 }
 
 pub async fn contest_ranklist(
-    Json(payload): Json<ContestRanklistPayload>,
+    Params(payload): Params<ContestRanklistPayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    auth::check_root_or_admin_or_player_of_contest(payload.id, conn, &cookies).await?;
+    principal.check(conn, payload.id, Action::Ranklist).await?;
     let models = record::Entity::find()
         .join(JoinType::InnerJoin, record::Relation::Problem.def())
         .join(JoinType::InnerJoin, problem::Relation::Contest.def())
-        .filter(problem::Column::ContestId.eq(payload.id))
+        .filter(problem::Column::ContestId.eq(payload.id.to_string()))
         .all(conn)
         .await?;
     let mut records = BTreeMap::<Uuid, BTreeMap<Uuid, (Uuid, String, DateTime)>>::new();
@@ -243,3 +304,457 @@ pub async fn contest_ranklist(
     }
     Ok(Json(ContestRanklistFeedback { records }))
 }
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct ContestArchive {
+    pub contest: ContestArchiveContest,
+    pub problems: Vec<ContestArchiveProblem>,
+    pub privileges: Vec<ContestArchivePrivilege>,
+    pub records: Vec<ContestArchiveRecord>,
+    pub users: BTreeMap<Uuid, String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct ContestArchiveContest {
+    pub nick: String,
+    pub description: String,
+    pub start: DateTime,
+    pub end: DateTime,
+    pub freeze_time: Option<DateTime>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct ContestArchiveProblem {
+    pub id: Uuid,
+    pub nick: String,
+    pub description: String,
+    pub limit_time: u32,
+    pub limit_memory: u32,
+    pub kind: String,
+    pub input_file: Option<String>,
+    pub output_file: Option<String>,
+    pub testdata: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct ContestArchivePrivilege {
+    pub user_id: Uuid,
+    pub kind: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct ContestArchiveRecord {
+    pub id: Uuid,
+    pub time: DateTime,
+    pub user_id: Uuid,
+    pub problem_id: Uuid,
+    pub code: String,
+    pub language: String,
+    pub result: String,
+    pub status: String,
+    pub testdata: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ContestExportPayload {
+    pub id: Uuid,
+    #[serde(default)]
+    pub records: bool,
+}
+
+pub async fn contest_export(
+    Params(payload): Params<ContestExportPayload>,
+    Extension(ref conn): Extension<DatabaseConnection>,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    principal.check_root()?;
+    let contest = contest::Entity::find_by_id(payload.id.to_string())
+        .one(conn)
+        .await?
+        .found()?;
+    let mut problems = problem::Entity::find()
+        .filter(problem::Column::ContestId.eq(payload.id.to_string()))
+        .all(conn)
+        .await?;
+    for model in problems.iter_mut() {
+        if model.testdata.is_empty() {
+            model.testdata = commit(conn, model.clone()).await?;
+        }
+    }
+    let privileges = privilege::Entity::find()
+        .filter(privilege::Column::ContestId.eq(payload.id.to_string()))
+        .all(conn)
+        .await?;
+    let records = if payload.records {
+        record::Entity::find()
+            .filter(record::Column::ProblemId.is_in(problems.iter().map(|x| x.id.clone())))
+            .order_by_asc(record::Column::Time)
+            .all(conn)
+            .await?
+    } else {
+        vec![]
+    };
+    let user_ids = privileges
+        .iter()
+        .map(|x| x.user_id.clone())
+        .chain(records.iter().map(|x| x.user_id.clone()))
+        .collect::<BTreeSet<_>>();
+    let users = user::Entity::find()
+        .filter(user::Column::Id.is_in(user_ids))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|x| (uuid!(x.id), x.name))
+        .collect();
+    let archive = ContestArchive {
+        contest: ContestArchiveContest {
+            nick: contest.nick,
+            description: contest.description,
+            start: contest.start,
+            end: contest.end,
+            freeze_time: contest.freeze_time,
+        },
+        problems: problems
+            .into_iter()
+            .map(|x| ContestArchiveProblem {
+                id: uuid!(x.id),
+                nick: x.nick,
+                description: x.description,
+                limit_time: x.limit_time as u32,
+                limit_memory: x.limit_memory as u32,
+                kind: x.kind,
+                input_file: x.input_file,
+                output_file: x.output_file,
+                testdata: x.testdata,
+            })
+            .collect(),
+        privileges: privileges
+            .into_iter()
+            .map(|x| ContestArchivePrivilege {
+                user_id: uuid!(x.user_id),
+                kind: x.kind,
+            })
+            .collect(),
+        records: records
+            .into_iter()
+            .map(|x| ContestArchiveRecord {
+                id: uuid!(x.id),
+                time: x.time,
+                user_id: uuid!(x.user_id),
+                problem_id: uuid!(x.problem_id),
+                code: x.code,
+                language: x.language,
+                result: x.result,
+                status: x.status,
+                testdata: x.testdata,
+            })
+            .collect(),
+        users,
+    };
+    let data = tokio::task::spawn_blocking(move || pack(&archive))
+        .await
+        .map_err(std::io::Error::other)?
+        .map_err(std::io::Error::other)?;
+    Ok((
+        [
+            (CONTENT_TYPE, "application/zip".to_string()),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.zip\"", payload.id),
+            ),
+        ],
+        data,
+    ))
+}
+
+#[derive(Deserialize, Default, JsonSchema)]
+pub struct ContestImportPayload {
+    #[serde(default)]
+    pub users: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ContestImportFeedback {
+    pub id: Uuid,
+    pub conflicts: Vec<String>,
+}
+
+pub async fn contest_import(
+    mut multipart: Multipart,
+    Extension(ref conn): Extension<DatabaseConnection>,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    principal.check_root()?;
+    let mut payload = ContestImportPayload::default();
+    let mut file: Option<Vec<u8>> = None;
+    while let Some(field) = multipart.next_field().await.good()? {
+        let name = field.name().good()?.to_string();
+        if name == "payload" {
+            let data = field.bytes().await.good()?;
+            let s = std::str::from_utf8(&data).good()?;
+            payload = serde_json::from_str(s).good()?;
+        } else if name == "file" {
+            file = Some(field.bytes().await.good()?.to_vec());
+        }
+    }
+    let file = file.found()?;
+    Ok(Json(import(conn, &principal, payload, file).await?))
+}
+
+// a contest archive carries every testdata version and output of its records, so it is bounded
+// more loosely than a single package
+const CONTEST_ENTRIES: usize = 1 << 16;
+const CONTEST_SIZE: u64 = 4 << 30;
+
+pub async fn import(
+    conn: &DatabaseConnection,
+    principal: &Principal,
+    payload: ContestImportPayload,
+    file: Vec<u8>,
+) -> AppResult<ContestImportFeedback> {
+    principal.check_root()?;
+    let entries = tokio::task::spawn_blocking(move || {
+        package::unzip_bounded(&file, &mut Bound::new(CONTEST_ENTRIES, CONTEST_SIZE))
+    })
+    .await
+    .map_err(std::io::Error::other)?
+    .good()?;
+    let archive: ContestArchive =
+        serde_json::from_slice(entries.get("contest.json").good()?).good()?;
+    for privilege in archive.privileges.iter() {
+        Role::from_str(&privilege.kind).good()?;
+    }
+    let mut testdata = BTreeMap::<(Uuid, String), Vec<(String, Vec<u8>)>>::new();
+    let mut outputs = BTreeMap::<Uuid, Vec<(String, Vec<u8>)>>::new();
+    for (path, data) in entries {
+        match path.split('/').collect::<Vec<_>>().as_slice() {
+            ["contest.json"] => {}
+            ["testdata", problem_id, hash, name] => {
+                let problem_id = Uuid::from_str(problem_id).good()?;
+                let (data, _) = prepare(name, data, false)?;
+                testdata
+                    .entry((problem_id, hash.to_string()))
+                    .or_default()
+                    .push((name.to_string(), data));
+            }
+            ["output", record_id, name] => {
+                let record_id = Uuid::from_str(record_id).good()?;
+                let name = check_output_name(name.to_string())?;
+                outputs.entry(record_id).or_default().push((name, data));
+            }
+            _ => {
+                return Err(AppError::BadRequest(Some(anyhow::anyhow!(
+                    "`{}` does not belong in a contest archive",
+                    path
+                ))))
+            }
+        }
+    }
+    for problem in archive.problems.iter() {
+        Kind::from_str(&problem.kind).good()?;
+        check_file_names(&problem.input_file, &problem.output_file)?;
+    }
+    let mut conflicts = vec![];
+    if contest::Entity::find()
+        .filter(contest::Column::Nick.eq(archive.contest.nick.clone()))
+        .one(conn)
+        .await?
+        .is_some()
+    {
+        conflicts.push(format!(
+            "a contest named `{}` already exists",
+            archive.contest.nick
+        ));
+    }
+    let mut users = BTreeMap::new();
+    for (id, name) in archive.users.iter() {
+        let name = payload.users.get(name).unwrap_or(name);
+        match user::Entity::find()
+            .filter(user::Column::Name.eq(name.clone()))
+            .one(conn)
+            .await?
+        {
+            Some(x) => {
+                users.insert(*id, x.id);
+            }
+            None => conflicts.push(format!(
+                "user `{}` does not exist, its privileges and records are skipped",
+                name
+            )),
+        }
+    }
+    // the database rolls back on failure, the files written so far are removed by hand
+    let txn = conn.begin().await?;
+    let mut written = vec![];
+    let result = async {
+        let contest_id = Uuid::new_v4();
+        let model = contest::ActiveModel {
+            id: Set(contest_id.to_string()),
+            nick: Set(archive.contest.nick),
+            description: Set(archive.contest.description),
+            start: Set(archive.contest.start),
+            end: Set(archive.contest.end),
+            freeze_time: Set(archive.contest.freeze_time),
+        }
+        .insert(&txn)
+        .await?;
+        audit::log(
+            &txn,
+            principal,
+            "contest_import",
+            contest_id,
+            None,
+            Some(&model),
+        )
+        .await?;
+        let mut problems = BTreeMap::new();
+        let mut hashes = BTreeMap::new();
+        for problem in archive.problems {
+            let problem_id = Uuid::new_v4();
+            let model = problem::ActiveModel {
+                id: Set(problem_id.to_string()),
+                nick: Set(problem.nick),
+                description: Set(problem.description),
+                limit_time: Set(problem.limit_time.into()),
+                limit_memory: Set(problem.limit_memory.into()),
+                contest_id: Set(contest_id.to_string()),
+                kind: Set(problem.kind),
+                input_file: Set(problem.input_file),
+                output_file: Set(problem.output_file),
+                testdata: Set(String::new()),
+            }
+            .insert(&txn)
+            .await?;
+            problems.insert(problem.id, problem_id);
+            written.push(testdata_dir(problem_id));
+            written.push(history_dir(problem_id));
+            if problem.testdata.is_empty() {
+                continue;
+            }
+            // older versions are replayed first so that the current one is left in place
+            let mut versions = testdata
+                .keys()
+                .filter(|x| x.0 == problem.id)
+                .cloned()
+                .collect::<Vec<_>>();
+            versions.sort_by_key(|x| x.1 == problem.testdata);
+            if versions.last().map(|x| &x.1) != Some(&problem.testdata) {
+                conflicts.push(format!(
+                    "testdata of problem `{}` is missing from the archive",
+                    model.nick
+                ));
+                continue;
+            }
+            for key in versions {
+                let files = testdata.remove(&key).unwrap_or_default();
+                let hash = tokio::task::spawn_blocking(move || {
+                    replace(problem_id, files)?;
+                    snapshot(problem_id)
+                })
+                .await
+                .map_err(std::io::Error::other)?
+                .map_err(std::io::Error::other)?;
+                hashes.insert(key, hash);
+            }
+            commit(&txn, model).await?;
+        }
+        for privilege in archive.privileges {
+            if let Some(user_id) = users.get(&privilege.user_id) {
+                privilege::ActiveModel {
+                    id: Set(Uuid::new_v4().to_string()),
+                    user_id: Set(user_id.clone()),
+                    contest_id: Set(contest_id.to_string()),
+                    kind: Set(privilege.kind),
+                }
+                .insert(&txn)
+                .await?;
+            }
+        }
+        for record in archive.records {
+            let (user_id, problem_id) =
+                match (users.get(&record.user_id), problems.get(&record.problem_id)) {
+                    (Some(user_id), Some(problem_id)) => (user_id, problem_id),
+                    _ => continue,
+                };
+            let record_id = Uuid::new_v4();
+            if let Some(files) = outputs.remove(&record.id) {
+                let dir = output_dir(record_id);
+                written.push(dir.clone());
+                tokio::fs::create_dir_all(&dir).await?;
+                for (name, data) in files {
+                    tokio::fs::write(format!("{}/{}", dir, name), data).await?;
+                }
+            }
+            let testdata = hashes
+                .get(&(record.problem_id, record.testdata))
+                .cloned()
+                .unwrap_or_default();
+            record::ActiveModel {
+                id: Set(record_id.to_string()),
+                time: Set(record.time),
+                user_id: Set(user_id.clone()),
+                problem_id: Set(problem_id.to_string()),
+                code: Set(record.code),
+                language: Set(record.language),
+                result: Set(record.result),
+                status: Set(record.status),
+                testdata: Set(testdata),
+            }
+            .insert(&txn)
+            .await?;
+        }
+        Ok::<_, AppError>(contest_id)
+    }
+    .await;
+    match result {
+        Ok(contest_id) => {
+            txn.commit().await?;
+            Ok(ContestImportFeedback {
+                id: contest_id,
+                conflicts,
+            })
+        }
+        Err(e) => {
+            let _ = txn.rollback().await;
+            for dir in written {
+                let _ = tokio::fs::remove_dir_all(dir).await;
+            }
+            Err(e)
+        }
+    }
+}
+
+fn pack(archive: &ContestArchive) -> anyhow::Result<Vec<u8>> {
+    let mut entries = BTreeMap::new();
+    entries.insert(
+        "contest.json".to_string(),
+        serde_json::to_vec_pretty(archive)?,
+    );
+    for problem in archive.problems.iter() {
+        let mut hashes = archive
+            .records
+            .iter()
+            .filter(|x| x.problem_id == problem.id)
+            .map(|x| x.testdata.clone())
+            .collect::<BTreeSet<_>>();
+        hashes.insert(problem.testdata.clone());
+        hashes.remove("");
+        for hash in hashes {
+            for (name, data) in restore(problem.id, &hash)? {
+                entries.insert(format!("testdata/{}/{}/{}", problem.id, hash, name), data);
+            }
+        }
+    }
+    for record in archive.records.iter() {
+        if let Ok(dir) = std::fs::read_dir(output_dir(record.id)) {
+            for entry in dir.filter_map(|x| x.ok()) {
+                let name = entry.file_name().to_string_lossy().to_string();
+                entries.insert(
+                    format!("output/{}/{}", record.id, name),
+                    std::fs::read(entry.path())?,
+                );
+            }
+        }
+    }
+    package::zip(&entries)
+}
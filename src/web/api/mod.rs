@@ -1,8 +1,15 @@
+pub mod audit;
 pub mod auth;
 pub mod contest;
+pub mod openapi;
 pub mod privilege;
 pub mod problem;
 pub mod record;
+pub mod similarity;
 pub mod submit;
 pub mod testdata;
+pub mod token;
 pub mod user;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,471 @@
+use super::audit::*;
+use super::auth::*;
+use super::contest::*;
+use super::privilege::*;
+use super::problem::*;
+use super::record::*;
+use super::similarity::*;
+use super::submit::*;
+use super::testdata::*;
+use super::token::*;
+use super::user::*;
+use crate::judger::lint::Health;
+use crate::judger::Tests;
+use crate::web::list::ListQuery;
+use crate::web::prelude::ErrorFeedback;
+use axum::handler::Handler;
+use axum::response::IntoResponse;
+use axum::routing::{on, MethodFilter};
+use axum::{Json, Router};
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+
+struct Spec {
+    generator: SchemaGenerator,
+    paths: Map<String, Value>,
+    router: Router,
+}
+
+impl Spec {
+    fn schema<T: JsonSchema>(&mut self) -> Value {
+        serde_json::to_value(self.generator.subschema_for::<T>()).unwrap()
+    }
+
+    // every field of a parameter struct becomes a query parameter, or a path parameter if named in the path
+    fn parameters<T: JsonSchema>(&mut self, path: &str) -> Value {
+        self.generator.subschema_for::<T>();
+        let definition =
+            serde_json::to_value(&self.generator.definitions()[&T::schema_name()]).unwrap();
+        let required = definition["required"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let mut parameters = vec![];
+        if let Some(properties) = definition["properties"].as_object() {
+            for (name, schema) in properties {
+                let in_path = path.contains(&format!("{{{}}}", name));
+                parameters.push(json!({
+                    "name": name,
+                    "in": if in_path { "path" } else { "query" },
+                    "required": in_path || required.contains(&json!(name)),
+                    "schema": schema,
+                }));
+            }
+        }
+        Value::Array(parameters)
+    }
+
+    // `{id}` in the document is `:id` in the router
+    fn route<H: Handler<T>, T: 'static>(&mut self, method: &str, path: &str, handler: H) {
+        let filter = match method {
+            "get" => MethodFilter::GET,
+            "post" => MethodFilter::POST,
+            "put" => MethodFilter::PUT,
+            "patch" => MethodFilter::PATCH,
+            "delete" => MethodFilter::DELETE,
+            _ => unreachable!("unsupported method `{}`", method),
+        };
+        let path = path.replace('{', ":").replace('}', "");
+        self.router = std::mem::take(&mut self.router).route(&path, on(filter, handler));
+    }
+
+    fn operation(&mut self, method: &str, path: &str, id: &str, mut operation: Value) {
+        let error = self.schema::<ErrorFeedback>();
+        operation["operationId"] = json!(id);
+        operation["tags"] = json!([path.split('/').nth(2)]);
+        operation["responses"]["default"] = json!({
+            "description": "error",
+            "content": { "application/json": { "schema": error } },
+        });
+        let item = self
+            .paths
+            .entry(path.to_string())
+            .or_insert_with(|| json!({}));
+        item[method] = operation;
+    }
+
+    fn read<P: JsonSchema, F: JsonSchema, T: 'static>(
+        &mut self,
+        path: &str,
+        id: &str,
+        handler: impl Handler<T>,
+    ) {
+        self.route("get", path, handler);
+        let parameters = self.parameters::<P>(path);
+        let feedback = self.schema::<F>();
+        self.operation(
+            "get",
+            path,
+            id,
+            json!({
+                "parameters": parameters,
+                "responses": { "200": response("application/json", feedback) },
+            }),
+        );
+    }
+
+    fn write<P: JsonSchema, F: JsonSchema, T: 'static>(
+        &mut self,
+        method: &str,
+        path: &str,
+        id: &str,
+        handler: impl Handler<T>,
+    ) {
+        self.route(method, path, handler);
+        let payload = self.schema::<P>();
+        let feedback = self.schema::<F>();
+        self.operation(
+            method,
+            path,
+            id,
+            json!({
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/json": { "schema": payload } },
+                },
+                "responses": { "200": response("application/json", feedback) },
+            }),
+        );
+    }
+
+    fn upload<P: JsonSchema, F: JsonSchema, T: 'static>(
+        &mut self,
+        path: &str,
+        id: &str,
+        handler: impl Handler<T>,
+    ) {
+        self.route("post", path, handler);
+        let payload = self.schema::<P>();
+        let feedback = self.schema::<F>();
+        self.operation(
+            "post",
+            path,
+            id,
+            json!({
+                "requestBody": {
+                    "required": true,
+                    "content": { "multipart/form-data": { "schema": multipart(payload) } },
+                },
+                "responses": { "200": response("application/json", feedback) },
+            }),
+        );
+    }
+
+    fn download<P: JsonSchema, T: 'static>(
+        &mut self,
+        path: &str,
+        id: &str,
+        content_type: &str,
+        handler: impl Handler<T>,
+    ) {
+        self.route("get", path, handler);
+        let parameters = self.parameters::<P>(path);
+        self.operation(
+            "get",
+            path,
+            id,
+            json!({
+                "parameters": parameters,
+                "responses": {
+                    "200": response(content_type, json!({ "type": "string", "format": "binary" })),
+                },
+            }),
+        );
+    }
+}
+
+fn response(content_type: &str, schema: Value) -> Value {
+    json!({
+        "description": "success",
+        "content": { content_type: { "schema": schema } },
+    })
+}
+
+// multipart requests carry the json payload in the `payload` field next to the uploaded `file`
+fn multipart(payload: Value) -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "payload": payload,
+            "file": { "type": "string", "format": "binary" },
+        },
+        "required": ["file"],
+    })
+}
+
+// the one table of the api, both the router and the document are built from it
+fn api() -> Spec {
+    let mut spec = Spec {
+        generator: SchemaGenerator::new(SchemaSettings::openapi3()),
+        paths: Map::new(),
+        router: Router::new(),
+    };
+    spec.read::<ListQuery, AuditListFeedback, _>("/api/audit/list", "audit_list", audit_list);
+    spec.write::<LoginPayload, LoginFeedback, _>(
+        "put",
+        "/api/auth/login",
+        "auth_login",
+        auth_login,
+    );
+    spec.write::<(), (), _>("put", "/api/auth/logout", "auth_logout", auth_logout);
+    spec.read::<ContestReadPayload, ContestReadFeedback, _>(
+        "/api/contest",
+        "contest_read",
+        contest_read,
+    );
+    spec.read::<ContestReadPayload, ContestReadFeedback, _>(
+        "/api/contest/{id}",
+        "contest_read_by_id",
+        contest_read,
+    );
+    spec.write::<ContestCreatePayload, ContestCreateFeedback, _>(
+        "post",
+        "/api/contest",
+        "contest_create",
+        contest_create,
+    );
+    spec.write::<ContestUpdatePayload, (), _>(
+        "patch",
+        "/api/contest",
+        "contest_update",
+        contest_update,
+    );
+    spec.write::<ContestDeletePayload, (), _>(
+        "delete",
+        "/api/contest",
+        "contest_delete",
+        contest_delete,
+    );
+    spec.read::<ListQuery, ContestListFeedback, _>(
+        "/api/contest/list",
+        "contest_list",
+        contest_list,
+    );
+    spec.read::<ContestRanklistPayload, ContestRanklistFeedback, _>(
+        "/api/contest/ranklist",
+        "contest_ranklist",
+        contest_ranklist,
+    );
+    spec.download::<ContestExportPayload, _>(
+        "/api/contest/export",
+        "contest_export",
+        "application/zip",
+        contest_export,
+    );
+    spec.upload::<ContestImportPayload, ContestImportFeedback, _>(
+        "/api/contest/import",
+        "contest_import",
+        contest_import,
+    );
+    spec.read::<PrivilegeReadPayload, PrivilegeReadFeedback, _>(
+        "/api/privilege",
+        "privilege_read",
+        privilege_read,
+    );
+    spec.write::<PrivilegeCreatePayload, (), _>(
+        "post",
+        "/api/privilege",
+        "privilege_create",
+        privilege_create,
+    );
+    spec.write::<PrivilegeDeletePayload, (), _>(
+        "delete",
+        "/api/privilege",
+        "privilege_delete",
+        privilege_delete,
+    );
+    spec.read::<ProblemReadPayload, ProblemReadFeedback, _>(
+        "/api/problem",
+        "problem_read",
+        problem_read,
+    );
+    spec.read::<ProblemReadPayload, ProblemReadFeedback, _>(
+        "/api/problem/{id}",
+        "problem_read_by_id",
+        problem_read,
+    );
+    spec.write::<ProblemCreatePayload, ProblemCreateFeedback, _>(
+        "post",
+        "/api/problem",
+        "problem_create",
+        problem_create,
+    );
+    spec.write::<ProblemUpdatePayload, (), _>(
+        "patch",
+        "/api/problem",
+        "problem_update",
+        problem_update,
+    );
+    spec.write::<ProblemDeletePayload, (), _>(
+        "delete",
+        "/api/problem",
+        "problem_delete",
+        problem_delete,
+    );
+    spec.read::<ListQuery, ProblemListFeedback, _>(
+        "/api/problem/list",
+        "problem_list",
+        problem_list,
+    );
+    spec.upload::<ProblemImportPayload, ProblemImportFeedback, _>(
+        "/api/problem/import",
+        "problem_import",
+        problem_import,
+    );
+    spec.download::<ProblemExportPayload, _>(
+        "/api/problem/export",
+        "problem_export",
+        "application/zip",
+        problem_export,
+    );
+    spec.read::<RecordReadPayload, RecordReadFeedback, _>(
+        "/api/record",
+        "record_read",
+        record_read,
+    );
+    spec.read::<RecordReadPayload, RecordReadFeedback, _>(
+        "/api/record/{id}",
+        "record_read_by_id",
+        record_read,
+    );
+    spec.write::<RecordDeletePayload, (), _>(
+        "delete",
+        "/api/record",
+        "record_delete",
+        record_delete,
+    );
+    spec.read::<ListQuery, RecordListFeedback, _>("/api/record/list", "record_list", record_list);
+    spec.write::<RecordRejudgePayload, (), _>(
+        "post",
+        "/api/record/rejudge",
+        "record_rejudge",
+        record_rejudge,
+    );
+    spec.read::<SimilarityReadPayload, SimilarityReadFeedback, _>(
+        "/api/similarity",
+        "similarity_read",
+        similarity_read,
+    );
+    spec.write::<SimilarityCreatePayload, SimilarityCreateFeedback, _>(
+        "post",
+        "/api/similarity",
+        "similarity_create",
+        similarity_create,
+    );
+    spec.read::<SimilarityListPayload, SimilarityListFeedback, _>(
+        "/api/similarity/list",
+        "similarity_list",
+        similarity_list,
+    );
+    spec.read::<UserReadPayload, UserReadFeedback, _>("/api/user", "user_read", user_read);
+    spec.read::<UserReadPayload, UserReadFeedback, _>(
+        "/api/user/{id}",
+        "user_read_by_id",
+        user_read,
+    );
+    spec.write::<UserCreatePayload, UserCreateFeedback, _>(
+        "post",
+        "/api/user",
+        "user_create",
+        user_create,
+    );
+    spec.write::<UserUpdatePayload, (), _>("patch", "/api/user", "user_update", user_update);
+    spec.write::<UserDeletePayload, (), _>("delete", "/api/user", "user_delete", user_delete);
+    spec.read::<ListQuery, UserListFeedback, _>("/api/user/list", "user_list", user_list);
+    spec.write::<SubmitPayload, SubmitFeedback, _>("post", "/api/submit", "submit", submit);
+    let outputs = multipart(spec.schema::<SubmitOutputPayload>());
+    spec.paths["/api/submit"]["post"]["requestBody"]["content"]["multipart/form-data"] =
+        json!({ "schema": outputs });
+    spec.read::<TestdataDownloadPayload, Vec<u8>, _>(
+        "/api/testdata",
+        "testdata_download",
+        testdata_download,
+    );
+    spec.upload::<TestdataUploadPayload, TestdataUploadFeedback, _>(
+        "/api/testdata",
+        "testdata_upload",
+        testdata_upload,
+    );
+    spec.write::<TestdataDeletePayload, (), _>(
+        "delete",
+        "/api/testdata",
+        "testdata_delete",
+        testdata_delete,
+    );
+    spec.read::<TestdataListPayload, TestdataListFeedback, _>(
+        "/api/testdata/list",
+        "testdata_list",
+        testdata_list,
+    );
+    spec.read::<TestdataCheckPayload, Health, _>(
+        "/api/testdata/check",
+        "testdata_check",
+        testdata_check,
+    );
+    spec.download::<TestdataArchiveDownloadPayload, _>(
+        "/api/testdata/archive",
+        "testdata_archive_download",
+        "application/gzip",
+        testdata_archive_download,
+    );
+    spec.upload::<TestdataArchiveUploadPayload, TestdataArchiveUploadFeedback, _>(
+        "/api/testdata/archive",
+        "testdata_archive_upload",
+        testdata_archive_upload,
+    );
+    spec.read::<TestdataVersionPayload, TestdataVersionFeedback, _>(
+        "/api/testdata/version",
+        "testdata_version",
+        testdata_version,
+    );
+    spec.write::<TestdataRollbackPayload, (), _>(
+        "post",
+        "/api/testdata/rollback",
+        "testdata_rollback",
+        testdata_rollback,
+    );
+    spec.write::<TokenCreatePayload, TokenCreateFeedback, _>(
+        "post",
+        "/api/token",
+        "token_create",
+        token_create,
+    );
+    spec.write::<TokenDeletePayload, (), _>("delete", "/api/token", "token_delete", token_delete);
+    spec.read::<TokenListPayload, TokenListFeedback, _>(
+        "/api/token/list",
+        "token_list",
+        token_list,
+    );
+    spec
+}
+
+pub fn routes() -> Router {
+    api().router
+}
+
+pub fn openapi() -> Value {
+    let mut spec = api();
+    // record results are stored as json text, the schema is published for decoding them
+    spec.schema::<Tests>();
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": spec.paths,
+        "components": {
+            "schemas": spec.generator.take_definitions(),
+            "securitySchemes": {
+                "session": { "type": "apiKey", "in": "cookie", "name": "session" },
+                "token": { "type": "http", "scheme": "bearer" },
+            },
+        },
+        "security": [{ "session": [] }, { "token": [] }],
+    })
+}
+
+pub async fn openapi_read() -> impl IntoResponse {
+    Json(openapi())
+}
@@ -1,73 +1,91 @@
-use super::auth;
+use super::audit;
+use super::auth::{Principal, Role};
 use crate::sys::schema::*;
+use crate::web::params::Params;
 use crate::web::prelude::*;
 use axum::response::IntoResponse;
 use axum::{Extension, Json};
+use schemars::JsonSchema;
 use sea_orm::prelude::*;
-use sea_orm::Set;
+use sea_orm::{Set, TransactionTrait};
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
-use tower_cookies::Cookies;
 use uuid::Uuid;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct PrivilegeCreatePayload {
     pub user_id: Uuid,
     pub contest_id: Uuid,
-    pub kind: String,
+    pub kind: Role,
 }
 
 pub async fn privilege_create(
     Json(payload): Json<PrivilegeCreatePayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    auth::check_root(conn, &cookies).await?;
-    privilege::ActiveModel {
+    principal.check_root()?;
+    if payload.kind == Role::Root {
+        return Err(AppError::BadRequest(Some(anyhow::anyhow!(
+            "root is not a contest role"
+        ))));
+    }
+    let txn = conn.begin().await?;
+    let model = privilege::ActiveModel {
         id: Set(Uuid::new_v4().to_string()),
         user_id: Set(payload.user_id.to_string()),
         contest_id: Set(payload.contest_id.to_string()),
-        kind: Set(payload.kind),
+        kind: Set(Into::<&'static str>::into(payload.kind).to_string()),
     }
-    .insert(conn)
+    .insert(&txn)
+    .await?;
+    audit::log(
+        &txn,
+        &principal,
+        "privilege_create",
+        payload.contest_id,
+        None,
+        Some(&model),
+    )
     .await?;
+    txn.commit().await?;
     Ok(Json(()))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct PrivilegeReadPayload {
     pub user_id: Option<Uuid>,
     pub contest_id: Option<Uuid>,
     pub kind: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct PrivilegeModel {
     pub user_id: Uuid,
     pub contest_id: Uuid,
     pub kind: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct PrivilegeReadFeedback {
     pub privilege: Vec<PrivilegeModel>,
 }
 
 pub async fn privilege_read(
-    Json(payload): Json<PrivilegeReadPayload>,
+    Params(payload): Params<PrivilegeReadPayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    auth::check_root(conn, &cookies).await?;
+    principal.check_root()?;
     let mut select = privilege::Entity::find();
     if let Some(user_id) = payload.user_id {
         select = select.filter(privilege::Column::UserId.eq(user_id.to_string()));
     }
     if let Some(contest_id) = payload.contest_id {
-        select = select.filter(privilege::Column::UserId.eq(contest_id.to_string()));
+        select = select.filter(privilege::Column::ContestId.eq(contest_id.to_string()));
     }
     if let Some(kind) = payload.kind {
-        select = select.filter(privilege::Column::UserId.eq(kind.to_string()));
+        select = select.filter(privilege::Column::Kind.eq(kind));
     }
     let model = select.all(conn).await?;
     Ok(Json(PrivilegeReadFeedback {
@@ -89,7 +107,7 @@ pub async fn privilege_read(
     }))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct PrivilegeDeletePayload {
     pub user_id: Uuid,
     pub contest_id: Uuid,
@@ -99,14 +117,28 @@ pub struct PrivilegeDeletePayload {
 pub async fn privilege_delete(
     Json(payload): Json<PrivilegeDeletePayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    auth::check_root(conn, &cookies).await?;
-    privilege::Entity::delete_many()
+    principal.check_root()?;
+    let models = privilege::Entity::find()
         .filter(privilege::Column::UserId.eq(payload.user_id.to_string()))
-        .filter(privilege::Column::UserId.eq(payload.contest_id.to_string()))
-        .filter(privilege::Column::UserId.eq(payload.kind))
-        .exec(conn)
+        .filter(privilege::Column::ContestId.eq(payload.contest_id.to_string()))
+        .filter(privilege::Column::Kind.eq(payload.kind))
+        .all(conn)
         .await?;
+    let txn = conn.begin().await?;
+    for model in models {
+        model.clone().delete(&txn).await?;
+        audit::log(
+            &txn,
+            &principal,
+            "privilege_delete",
+            payload.contest_id,
+            Some(&model),
+            None,
+        )
+        .await?;
+    }
+    txn.commit().await?;
     Ok(Json(()))
 }
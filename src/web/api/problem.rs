@@ -1,25 +1,39 @@
-use super::auth;
+use super::audit;
+use super::auth::{Action, Principal};
+use super::testdata::{commit, prepare, replace};
+use crate::judger::version::{history_dir, restore};
+use crate::judger::{testdata_dir, Kind};
+use crate::package::{self, Format, Package};
 use crate::sys::schema::*;
+use crate::web::list::ListQuery;
+use crate::web::params::Params;
 use crate::web::prelude::*;
+use axum::extract::Multipart;
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
 use axum::response::IntoResponse;
 use axum::{Extension, Json};
+use schemars::JsonSchema;
 use sea_orm::prelude::*;
-use sea_orm::{JoinType, QueryOrder, QuerySelect, Set};
+use sea_orm::sea_query::Query;
+use sea_orm::{Set, TransactionTrait};
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
-use tower_cookies::Cookies;
 use uuid::Uuid;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct ProblemCreatePayload {
     pub nick: String,
     pub description: String,
     pub limit_time: u32,
     pub limit_memory: u32,
     pub contest_id: Uuid,
+    #[serde(default)]
+    pub kind: Kind,
+    pub input_file: Option<String>,
+    pub output_file: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ProblemCreateFeedback {
     pub id: Uuid,
 }
@@ -27,43 +41,67 @@ pub struct ProblemCreateFeedback {
 pub async fn problem_create(
     Json(payload): Json<ProblemCreatePayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    auth::check_root_or_admin_of_contest(payload.contest_id, conn, &cookies).await?;
+    principal
+        .check(conn, payload.contest_id, Action::Edit)
+        .await?;
+    check_file_names(&payload.input_file, &payload.output_file)?;
+    let txn = conn.begin().await?;
     let model = problem::ActiveModel {
         id: Set(Uuid::new_v4().to_string()),
         nick: Set(payload.nick),
         description: Set(payload.description),
-        limit_time: Set(payload.limit_time),
-        limit_memory: Set(payload.limit_memory),
+        limit_time: Set(payload.limit_time.into()),
+        limit_memory: Set(payload.limit_memory.into()),
         contest_id: Set(payload.contest_id.to_string()),
+        kind: Set(Into::<&'static str>::into(payload.kind).to_string()),
+        input_file: Set(payload.input_file),
+        output_file: Set(payload.output_file),
+        testdata: Set(String::new()),
     }
-    .insert(conn)
+    .insert(&txn)
     .await?;
+    audit::log(
+        &txn,
+        &principal,
+        "problem_create",
+        uuid!(model.id),
+        None,
+        Some(&model),
+    )
+    .await?;
+    txn.commit().await?;
     Ok(Json(ProblemCreateFeedback {
         id: uuid!(model.id),
     }))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct ProblemUpdatePayload {
     pub id: Uuid,
     pub nick: Option<String>,
     pub description: Option<String>,
     pub limit_time: Option<u32>,
     pub limit_memory: Option<u32>,
+    pub kind: Option<Kind>,
+    pub input_file: Option<String>,
+    pub output_file: Option<String>,
 }
 
 pub async fn problem_update(
     Json(payload): Json<ProblemUpdatePayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
     let model = problem::Entity::find_by_id(payload.id.to_string())
         .one(conn)
         .await?
         .found()?;
-    auth::check_root_or_admin_of_contest(uuid!(model.contest_id), conn, &cookies).await?;
+    principal
+        .check(conn, uuid!(model.contest_id), Action::Edit)
+        .await?;
+    let before = model.clone();
     let mut model: problem::ActiveModel = model.into();
     if let Some(nick) = payload.nick {
         model.nick = Set(nick);
@@ -72,49 +110,84 @@ pub async fn problem_update(
         model.description = Set(description);
     }
     if let Some(limit_time) = payload.limit_time {
-        model.limit_time = Set(limit_time);
+        model.limit_time = Set(limit_time.into());
     }
     if let Some(limit_memory) = payload.limit_memory {
-        model.limit_memory = Set(limit_memory);
+        model.limit_memory = Set(limit_memory.into());
+    }
+    if let Some(kind) = payload.kind {
+        model.kind = Set(Into::<&'static str>::into(kind).to_string());
     }
-    model.update(conn).await?;
+    let input_file = match payload.input_file {
+        Some(x) => Some(x).filter(|x| !x.is_empty()),
+        None => before.input_file.clone(),
+    };
+    let output_file = match payload.output_file {
+        Some(x) => Some(x).filter(|x| !x.is_empty()),
+        None => before.output_file.clone(),
+    };
+    check_file_names(&input_file, &output_file)?;
+    model.input_file = Set(input_file);
+    model.output_file = Set(output_file);
+    let txn = conn.begin().await?;
+    let model = model.update(&txn).await?;
+    audit::log(
+        &txn,
+        &principal,
+        "problem_update",
+        payload.id,
+        Some(&before),
+        Some(&model),
+    )
+    .await?;
+    txn.commit().await?;
     Ok(Json(()))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct ProblemReadPayload {
     pub id: Uuid,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ProblemReadFeedback {
     pub nick: String,
     pub description: String,
     pub limit_time: u32,
     pub limit_memory: u32,
     pub contest_id: Uuid,
+    pub kind: String,
+    pub input_file: Option<String>,
+    pub output_file: Option<String>,
+    pub testdata: String,
 }
 
 pub async fn problem_read(
-    Json(payload): Json<ProblemReadPayload>,
+    Params(payload): Params<ProblemReadPayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
     let model = problem::Entity::find_by_id(payload.id.to_string())
         .one(conn)
         .await?
         .found()?;
-    auth::check_root_or_admin_or_player_of_contest(uuid!(model.contest_id), conn, &cookies).await?;
+    principal
+        .check(conn, uuid!(model.contest_id), Action::View)
+        .await?;
     Ok(Json(ProblemReadFeedback {
         nick: model.nick,
         description: model.description,
-        limit_time: model.limit_time,
-        limit_memory: model.limit_memory,
+        limit_time: model.limit_time as u32,
+        limit_memory: model.limit_memory as u32,
         contest_id: uuid!(model.contest_id),
+        kind: model.kind,
+        input_file: model.input_file,
+        output_file: model.output_file,
+        testdata: model.testdata,
     }))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct ProblemDeletePayload {
     pub id: Uuid,
 }
@@ -122,18 +195,31 @@ pub struct ProblemDeletePayload {
 pub async fn problem_delete(
     Json(payload): Json<ProblemDeletePayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
     let model = problem::Entity::find_by_id(payload.id.to_string())
         .one(conn)
         .await?
         .found()?;
-    auth::check_root_or_admin_or_player_of_contest(uuid!(model.contest_id), conn, &cookies).await?;
-    model.delete(conn).await?;
+    principal
+        .check(conn, uuid!(model.contest_id), Action::Edit)
+        .await?;
+    let txn = conn.begin().await?;
+    model.clone().delete(&txn).await?;
+    audit::log(
+        &txn,
+        &principal,
+        "problem_delete",
+        payload.id,
+        Some(&model),
+        None,
+    )
+    .await?;
+    txn.commit().await?;
     Ok(Json(()))
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ProblemModel {
     pub id: Uuid,
     pub nick: String,
@@ -141,50 +227,48 @@ pub struct ProblemModel {
     pub limit_time: u32,
     pub limit_memory: u32,
     pub contest_id: Uuid,
+    pub kind: String,
+    pub input_file: Option<String>,
+    pub output_file: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ProblemListFeedback {
     pub problems: Vec<ProblemModel>,
+    pub total: u64,
 }
 
 pub async fn problem_list(
+    list: ListQuery,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    let user_id = auth::check_user(conn, &cookies).await?;
-    if auth::test_root(conn, &cookies).await.is_ok() {
-        let problems = problem::Entity::find()
-            .order_by_asc(problem::Column::Nick)
-            .all(conn)
-            .await?
-            .into_iter()
-            .map(
-                |problem::Model {
-                     id,
-                     nick,
-                     description,
-                     limit_time,
-                     limit_memory,
-                     contest_id,
-                 }| ProblemModel {
-                    id: uuid!(id),
-                    nick,
-                    description,
-                    limit_time,
-                    limit_memory,
-                    contest_id: uuid!(contest_id),
-                },
-            )
-            .collect();
-        return Ok(Json(ProblemListFeedback { problems }));
+    let user_id = principal.id();
+    let mut select = problem::Entity::find();
+    if !principal.is_root() {
+        select = select.filter(
+            problem::Column::ContestId.in_subquery(
+                Query::select()
+                    .column(privilege::Column::ContestId)
+                    .from(privilege::Entity)
+                    .and_where(privilege::Column::UserId.eq(user_id.to_string()))
+                    .to_owned(),
+            ),
+        );
     }
-    let problems = problem::Entity::find()
-        .join(JoinType::InnerJoin, contest::Relation::Problem.def())
-        .join(JoinType::InnerJoin, privilege::Relation::Contest.def())
-        .filter(privilege::Column::UserId.eq(user_id.to_string()))
-        .all(conn)
-        .await?
+    if let Some(contest_id) = list.contest_id {
+        select = select.filter(problem::Column::ContestId.eq(contest_id.to_string()));
+    }
+    let select = list.sort(
+        select,
+        &[
+            ("nick", problem::Column::Nick),
+            ("limit_time", problem::Column::LimitTime),
+            ("limit_memory", problem::Column::LimitMemory),
+        ],
+    )?;
+    let (models, total) = list.fetch(select, conn).await?;
+    let problems = models
         .into_iter()
         .map(
             |problem::Model {
@@ -194,15 +278,219 @@ pub async fn problem_list(
                  limit_time,
                  limit_memory,
                  contest_id,
+                 kind,
+                 input_file,
+                 output_file,
+                 testdata: _,
              }| ProblemModel {
                 id: uuid!(id),
                 nick,
                 description,
-                limit_time,
-                limit_memory,
+                limit_time: limit_time as u32,
+                limit_memory: limit_memory as u32,
                 contest_id: uuid!(contest_id),
+                kind,
+                input_file,
+                output_file,
             },
         )
         .collect();
-    Ok(Json(ProblemListFeedback { problems }))
+    Ok(Json(ProblemListFeedback { problems, total }))
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ProblemImportPayload {
+    pub contest_id: Uuid,
+    #[serde(default)]
+    pub format: Format,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ProblemImportFeedback {
+    pub ids: Vec<Uuid>,
+    pub warnings: Vec<String>,
+}
+
+pub async fn problem_import(
+    mut multipart: Multipart,
+    Extension(ref conn): Extension<DatabaseConnection>,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    let mut payload: Option<ProblemImportPayload> = None;
+    let mut file: Option<Vec<u8>> = None;
+    while let Some(field) = multipart.next_field().await.good()? {
+        let name = field.name().good()?.to_string();
+        if name == "payload" {
+            let data = field.bytes().await.good()?;
+            let s = std::str::from_utf8(&data).good()?;
+            payload = Some(serde_json::from_str(s).good()?);
+        } else if name == "file" {
+            file = Some(field.bytes().await.good()?.to_vec());
+        }
+    }
+    let payload = payload.found()?;
+    let file = file.found()?;
+    principal
+        .check(conn, payload.contest_id, Action::Edit)
+        .await?;
+    let format = payload.format;
+    let (packages, mut warnings) =
+        tokio::task::spawn_blocking(move || package::import(format, &file))
+            .await
+            .map_err(std::io::Error::other)?
+            .good()?;
+    let mut prepared = vec![];
+    for package in packages {
+        check_file_names(&package.input_file, &package.output_file)?;
+        let mut files = vec![];
+        for (name, data) in package.files.iter() {
+            let (data, lints) = prepare(name, data.clone(), false)?;
+            warnings.extend(
+                lints
+                    .into_iter()
+                    .map(|x| format!("`{}`: {}", x.name, x.message)),
+            );
+            files.push((name.clone(), data));
+        }
+        prepared.push((package, files));
+    }
+    // the database rolls back on failure, the files written so far are removed by hand
+    let txn = conn.begin().await?;
+    let mut written = vec![];
+    let result = async {
+        let mut ids = vec![];
+        for (package, files) in prepared {
+            let model = problem::ActiveModel {
+                id: Set(Uuid::new_v4().to_string()),
+                nick: Set(package.nick),
+                description: Set(package.description),
+                limit_time: Set(package.limit_time.into()),
+                limit_memory: Set(package.limit_memory.into()),
+                contest_id: Set(payload.contest_id.to_string()),
+                kind: Set(Into::<&'static str>::into(package.kind).to_string()),
+                input_file: Set(package.input_file),
+                output_file: Set(package.output_file),
+                testdata: Set(String::new()),
+            }
+            .insert(&txn)
+            .await?;
+            let problem_id = uuid!(model.id);
+            written.push(testdata_dir(problem_id));
+            written.push(history_dir(problem_id));
+            tokio::task::spawn_blocking(move || replace(problem_id, files))
+                .await
+                .map_err(std::io::Error::other)??;
+            let testdata = commit(&txn, model.clone()).await?;
+            let model = problem::Model { testdata, ..model };
+            audit::log(
+                &txn,
+                &principal,
+                "problem_import",
+                problem_id,
+                None,
+                Some(&model),
+            )
+            .await?;
+            ids.push(problem_id);
+        }
+        Ok::<_, AppError>(ids)
+    }
+    .await;
+    let ids = match result {
+        Ok(ids) => {
+            txn.commit().await?;
+            ids
+        }
+        Err(e) => {
+            let _ = txn.rollback().await;
+            for dir in written {
+                let _ = tokio::fs::remove_dir_all(dir).await;
+            }
+            return Err(e);
+        }
+    };
+    Ok(Json(ProblemImportFeedback { ids, warnings }))
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ProblemExportPayload {
+    pub id: Uuid,
+    #[serde(default)]
+    pub format: Format,
+}
+
+pub async fn problem_export(
+    Params(payload): Params<ProblemExportPayload>,
+    Extension(ref conn): Extension<DatabaseConnection>,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    let model = problem::Entity::find_by_id(payload.id.to_string())
+        .one(conn)
+        .await?
+        .found()?;
+    principal
+        .check(conn, uuid!(model.contest_id), Action::Edit)
+        .await?;
+    let format = payload.format;
+    let testdata = if model.testdata.is_empty() {
+        commit(conn, model.clone()).await?
+    } else {
+        model.testdata.clone()
+    };
+    let data = tokio::task::spawn_blocking(move || {
+        let files = restore(payload.id, &testdata)?;
+        let package = Package {
+            nick: model.nick,
+            description: model.description,
+            limit_time: model.limit_time as u32,
+            limit_memory: model.limit_memory as u32,
+            kind: Kind::from_str(&model.kind).unwrap_or_default(),
+            input_file: model.input_file,
+            output_file: model.output_file,
+            files: files.into_iter().collect(),
+        };
+        package::export(format, &package)
+    })
+    .await
+    .map_err(std::io::Error::other)?
+    .map_err(std::io::Error::other)?;
+    let (content_type, extension) = match format {
+        Format::Fps => ("application/xml", "xml"),
+        _ => ("application/zip", "zip"),
+    };
+    Ok((
+        [
+            (CONTENT_TYPE, content_type.to_string()),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.{}\"", payload.id, extension),
+            ),
+        ],
+        data,
+    ))
+}
+
+// solutions with file io run in a directory holding only these two files
+pub fn check_file_names(
+    input_file: &Option<String>,
+    output_file: &Option<String>,
+) -> AppResult<()> {
+    for name in [input_file, output_file] {
+        match name {
+            Some(x) if x.is_empty() || x.starts_with('.') || x.contains(['/', '\\']) => {
+                return Err(AppError::BadRequest(Some(anyhow::anyhow!(
+                    "`{}` is not a valid file name",
+                    x
+                ))))
+            }
+            _ => {}
+        }
+    }
+    match (input_file, output_file) {
+        (Some(x), Some(y)) if x == y => Err(AppError::BadRequest(Some(anyhow::anyhow!(
+            "the input and output files are both `{}`",
+            x
+        )))),
+        _ => Ok(()),
+    }
 }
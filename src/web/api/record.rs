@@ -1,36 +1,46 @@
-use super::auth;
+use super::audit;
+use super::auth::{self, Action, Principal, Role};
+use super::submit::{check_draining, dispatch, judge_of};
+use super::testdata::commit;
+use crate::judger::version::version;
+use crate::judger::{output_dir, Judger};
 use crate::sys::schema::*;
+use crate::web::list::ListQuery;
+use crate::web::params::Params;
 use crate::web::prelude::*;
 use axum::response::IntoResponse;
 use axum::{Extension, Json};
-use chrono::Utc;
+use schemars::JsonSchema;
 use sea_orm::prelude::*;
-use sea_orm::{JoinType, QueryOrder, QuerySelect};
+use sea_orm::sea_query::{Expr, Query};
+use sea_orm::{Condition, Set, TransactionTrait};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::str::FromStr;
-use tower_cookies::Cookies;
 use uuid::Uuid;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct RecordReadPayload {
     pub id: Uuid,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct RecordReadFeedback {
     pub time: DateTime,
     pub user_id: Uuid,
     pub problem_id: Uuid,
     pub code: String,
     pub language: String,
+    #[schemars(description = "JSON encoded `Tests`")]
     pub result: String,
     pub status: String,
+    pub testdata: String,
 }
 
 pub async fn record_read(
-    Json(payload): Json<RecordReadPayload>,
+    Params(payload): Params<RecordReadPayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
     let model = record::Entity::find_by_id(payload.id.to_string())
         .one(conn)
@@ -38,47 +48,23 @@ pub async fn record_read(
         .found()?;
     let model_problem = problem::Entity::find_by_id(model.problem_id.clone())
         .one(conn)
+        .await?
+        .allow()?;
+    let roles = principal
+        .check(conn, uuid!(model_problem.contest_id), Action::View)
         .await?;
-    let contest_id;
-    let user_id;
-    if let Some(model_problem) = model_problem {
-        contest_id = uuid!(model_problem.contest_id);
-        user_id = auth::test_root_or_admin_or_player_of_contest(contest_id, conn, &cookies).await?;
-    } else {
-        return Err(AppError::Forbidden(None));
-    }
-    let now = Utc::now().naive_local();
-    let flag = contest::Entity::find()
-        .filter(contest::Column::Start.gte(now))
-        .filter(contest::Column::End.lt(now))
-        .one(conn)
-        .await
-        .map(|x| x.is_some())
-        .unwrap_or(false);
-    if flag {
-        let model_contest = contest::Entity::find_by_id(contest_id.to_string())
+    let mut visible = auth::permits(&roles, Action::Records);
+    if visible && auth::frozen(&roles) {
+        let model_contest = contest::Entity::find_by_id(model_problem.contest_id.clone())
             .one(conn)
-            .await?;
-        if let Some(model_contest) = model_contest {
-            let expected = uuid!(model.user_id);
-            match user_id {
-                Ok(_root_id) => (),
-                Err(Ok(_admin_id)) => (),
-                Err(Err(player_id)) => {
-                    if expected != player_id {
-                        return Err(AppError::Forbidden(None));
-                    }
-                    if now < model_contest.start {
-                        return Err(AppError::Forbidden(None));
-                    }
-                    if model_contest.end < now {
-                        return Err(AppError::Forbidden(None));
-                    }
-                }
-            }
-        } else {
-            return Err(AppError::Forbidden(None));
-        }
+            .await?
+            .allow()?;
+        visible = model_contest.freeze_time.is_none_or(|x| model.time < x);
+    }
+    // without access to all records, contestants still see their own submissions
+    let own = uuid!(model.user_id) == principal.id();
+    if !(visible || own && auth::permits(&roles, Action::Submit)) {
+        return Err(AppError::Forbidden(None));
     }
     Ok(Json(RecordReadFeedback {
         time: model.time,
@@ -88,10 +74,11 @@ pub async fn record_read(
         language: model.language,
         result: model.result,
         status: model.status,
+        testdata: model.testdata,
     }))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct RecordDeletePayload {
     pub id: Uuid,
 }
@@ -99,72 +86,206 @@ pub struct RecordDeletePayload {
 pub async fn record_delete(
     Json(payload): Json<RecordDeletePayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    principal.check_root()?;
+    let model = record::Entity::find_by_id(payload.id.to_string())
+        .one(conn)
+        .await?
+        .found()?;
+    let before = json!({
+        "user_id": model.user_id,
+        "problem_id": model.problem_id,
+        "status": model.status,
+    });
+    let txn = conn.begin().await?;
+    model.delete(&txn).await?;
+    audit::log(
+        &txn,
+        &principal,
+        "record_delete",
+        payload.id,
+        Some(&before),
+        None,
+    )
+    .await?;
+    txn.commit().await?;
+    // only output-only records have one
+    let _ = tokio::fs::remove_dir_all(output_dir(payload.id)).await;
+    Ok(Json(()))
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct RecordRejudgePayload {
+    pub id: Uuid,
+    pub testdata: Option<String>,
+}
+
+pub async fn record_rejudge(
+    Json(payload): Json<RecordRejudgePayload>,
+    Extension(conn): Extension<DatabaseConnection>,
+    Extension(judger): Extension<Judger>,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    auth::check_root(conn, &cookies).await?;
-    record::Entity::delete_by_id(payload.id.to_string())
-        .exec(conn)
+    check_draining(&judger)?;
+    let model = record::Entity::find_by_id(payload.id.to_string())
+        .one(&conn)
+        .await?
+        .found()?;
+    let model_problem = problem::Entity::find_by_id(model.problem_id.clone())
+        .one(&conn)
+        .await?
+        .found()?;
+    let problem_id = uuid!(model_problem.id);
+    principal
+        .check(&conn, uuid!(model_problem.contest_id), Action::Rejudge)
         .await?;
+    let testdata = match payload.testdata {
+        Some(testdata) => {
+            version(problem_id, &testdata).found()?;
+            testdata
+        }
+        None if model_problem.testdata.is_empty() => commit(&conn, model_problem.clone()).await?,
+        None => model_problem.testdata.clone(),
+    };
+    let before = json!({ "status": model.status, "testdata": model.testdata });
+    let mut model: record::ActiveModel = model.into();
+    model.result = Set("{}".to_string());
+    model.status = Set("waiting".to_string());
+    model.testdata = Set(testdata.clone());
+    let txn = conn.begin().await?;
+    let model = model.update(&txn).await?;
+    audit::log(
+        &txn,
+        &principal,
+        "record_rejudge",
+        payload.id,
+        Some(&before),
+        Some(&json!({ "status": model.status, "testdata": model.testdata })),
+    )
+    .await?;
+    txn.commit().await?;
+    let judge = judge_of(
+        payload.id,
+        testdata,
+        model_problem,
+        model.language.clone(),
+        model.code.clone(),
+    );
+    dispatch(conn, judger, model, judge);
     Ok(Json(()))
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct RecordModel {
     pub id: Uuid,
     pub time: DateTime,
     pub user_id: Uuid,
     pub problem_id: Uuid,
     pub language: String,
-    pub result: String,
     pub status: String,
+    pub testdata: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct RecordListFeedback {
     records: Vec<RecordModel>,
+    total: u64,
 }
 
 pub async fn record_list(
+    list: ListQuery,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    let user_id = auth::check_user(conn, &cookies).await?;
-    if auth::test_root(conn, &cookies).await.is_ok() {
-        let records = record::Entity::find()
-            .order_by_asc(record::Column::Time)
-            .all(conn)
-            .await?
+    let mut select = record::Entity::find();
+    if !principal.is_root() {
+        let user_id = principal.id().to_string();
+        let problems = |kinds: Vec<&str>| {
+            Query::select()
+                .column((problem::Entity, problem::Column::Id))
+                .from(problem::Entity)
+                .and_where(
+                    Expr::tbl(problem::Entity, problem::Column::ContestId).in_subquery(
+                        Query::select()
+                            .column(privilege::Column::ContestId)
+                            .from(privilege::Entity)
+                            .and_where(privilege::Column::UserId.eq(user_id.clone()))
+                            .and_where(privilege::Column::Kind.is_in(kinds))
+                            .to_owned(),
+                    ),
+                )
+                .to_owned()
+        };
+        let observer: &str = Role::Observer.into();
+        let unfrozen = auth::kinds(Action::Records)
             .into_iter()
-            .map(
-                |record::Model {
-                     id,
-                     time,
-                     user_id,
-                     problem_id,
-                     code: _,
-                     language,
-                     result,
-                     status,
-                 }| RecordModel {
-                    id: uuid!(id),
-                    time,
-                    user_id: uuid!(user_id),
-                    problem_id: uuid!(problem_id),
-                    language,
-                    result,
-                    status,
-                },
-            )
+            .filter(|x| *x != observer)
             .collect();
-        return Ok(Json(RecordListFeedback { records }));
+        // observers see records only up to the freeze of their contest
+        let frozen = problems(vec![observer])
+            .inner_join(
+                contest::Entity,
+                Expr::tbl(contest::Entity, contest::Column::Id)
+                    .equals(problem::Entity, problem::Column::ContestId),
+            )
+            .cond_where(
+                Condition::any()
+                    .add(Expr::tbl(contest::Entity, contest::Column::FreezeTime).is_null())
+                    .add(
+                        Expr::tbl(record::Entity, record::Column::Time)
+                            .less_than(Expr::tbl(contest::Entity, contest::Column::FreezeTime)),
+                    ),
+            )
+            .to_owned();
+        select = select.filter(
+            Condition::any()
+                .add(record::Column::ProblemId.in_subquery(problems(unfrozen)))
+                .add(record::Column::ProblemId.in_subquery(frozen))
+                .add(
+                    Condition::all()
+                        .add(record::Column::UserId.eq(user_id.clone()))
+                        .add(
+                            record::Column::ProblemId
+                                .in_subquery(problems(auth::kinds(Action::Submit))),
+                        ),
+                ),
+        );
     }
-    let records = record::Entity::find()
-        .join(JoinType::InnerJoin, problem::Relation::Record.def())
-        .join(JoinType::InnerJoin, contest::Relation::Problem.def())
-        .join(JoinType::InnerJoin, privilege::Relation::Contest.def())
-        .filter(privilege::Column::UserId.eq(user_id.to_string()))
-        .all(conn)
-        .await?
+    if let Some(user_id) = list.user_id {
+        select = select.filter(record::Column::UserId.eq(user_id.to_string()));
+    }
+    if let Some(problem_id) = list.problem_id {
+        select = select.filter(record::Column::ProblemId.eq(problem_id.to_string()));
+    }
+    if let Some(contest_id) = list.contest_id {
+        select = select.filter(
+            record::Column::ProblemId.in_subquery(
+                Query::select()
+                    .column(problem::Column::Id)
+                    .from(problem::Entity)
+                    .and_where(problem::Column::ContestId.eq(contest_id.to_string()))
+                    .to_owned(),
+            ),
+        );
+    }
+    if let Some(ref status) = list.status {
+        select = select.filter(record::Column::Status.eq(status.clone()));
+    }
+    if let Some(ref language) = list.language {
+        select = select.filter(record::Column::Language.eq(language.clone()));
+    }
+    let select = list.range(select, record::Column::Time);
+    let select = list.sort(
+        select,
+        &[
+            ("time", record::Column::Time),
+            ("status", record::Column::Status),
+            ("language", record::Column::Language),
+        ],
+    )?;
+    let (models, total) = list.fetch(select, conn).await?;
+    let records = models
         .into_iter()
         .map(
             |record::Model {
@@ -174,18 +295,19 @@ pub async fn record_list(
                  problem_id,
                  code: _,
                  language,
-                 result,
+                 result: _,
                  status,
+                 testdata,
              }| RecordModel {
                 id: uuid!(id),
                 time,
                 user_id: uuid!(user_id),
                 problem_id: uuid!(problem_id),
                 language,
-                result,
                 status,
+                testdata,
             },
         )
         .collect();
-    Ok(Json(RecordListFeedback { records }))
+    Ok(Json(RecordListFeedback { records, total }))
 }
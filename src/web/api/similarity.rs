@@ -0,0 +1,200 @@
+use super::auth::{Action, Principal};
+use crate::similarity::{analyze, Report, Submission};
+use crate::sys::schema::*;
+use crate::web::params::Params;
+use crate::web::prelude::*;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use chrono::Utc;
+use schemars::JsonSchema;
+use sea_orm::prelude::*;
+use sea_orm::sea_query::Query;
+use sea_orm::{QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use uuid::Uuid;
+
+pub const THRESHOLD_DEFAULT: f64 = 0.5;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct SimilarityCreatePayload {
+    pub contest_id: Uuid,
+    pub problem_id: Option<Uuid>,
+    pub threshold: Option<f64>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SimilarityCreateFeedback {
+    pub id: Uuid,
+}
+
+pub async fn similarity_create(
+    Json(payload): Json<SimilarityCreatePayload>,
+    Extension(conn): Extension<DatabaseConnection>,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    principal
+        .check(&conn, payload.contest_id, Action::Similarity)
+        .await?;
+    if let Some(problem_id) = payload.problem_id {
+        let model = problem::Entity::find_by_id(problem_id.to_string())
+            .one(&conn)
+            .await?
+            .found()?;
+        if uuid!(model.contest_id) != payload.contest_id {
+            return Err(AppError::BadRequest(None));
+        }
+    }
+    let model = similarity::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        time: Set(Utc::now().naive_local()),
+        user_id: Set(principal.id().to_string()),
+        contest_id: Set(payload.contest_id.to_string()),
+        problem_id: Set(payload.problem_id.map(|x| x.to_string())),
+        status: Set("running".to_string()),
+        report: Set("{}".to_string()),
+    }
+    .insert(&conn)
+    .await?;
+    let id = uuid!(model.id);
+    let threshold = payload.threshold.unwrap_or(THRESHOLD_DEFAULT);
+    tokio::spawn(async move {
+        let report = match submissions(&conn, &model).await {
+            Ok(submissions) => {
+                tokio::task::spawn_blocking(move || analyze(&submissions, threshold))
+                    .await
+                    .ok()
+            }
+            Err(_) => None,
+        };
+        let mut model: similarity::ActiveModel = model.into();
+        match report {
+            Some(report) => {
+                model.status = Set("done".to_string());
+                model.report = Set(serde_json::to_string(&report).unwrap());
+            }
+            None => model.status = Set("failed".to_string()),
+        }
+        model.save(&conn).await.unwrap();
+    });
+    Ok(Json(SimilarityCreateFeedback { id }))
+}
+
+// only the latest submission of every user, problem and language takes part
+async fn submissions(
+    conn: &DatabaseConnection,
+    model: &similarity::Model,
+) -> AppResult<Vec<Submission>> {
+    let mut select = record::Entity::find()
+        .filter(record::Column::Language.ne("output"))
+        .order_by_asc(record::Column::Time);
+    select = match model.problem_id {
+        Some(ref problem_id) => select.filter(record::Column::ProblemId.eq(problem_id.clone())),
+        None => select.filter(
+            record::Column::ProblemId.in_subquery(
+                Query::select()
+                    .column(problem::Column::Id)
+                    .from(problem::Entity)
+                    .and_where(problem::Column::ContestId.eq(model.contest_id.clone()))
+                    .to_owned(),
+            ),
+        ),
+    };
+    let mut latest = BTreeMap::new();
+    for x in select.all(conn).await? {
+        latest.insert(
+            (x.user_id.clone(), x.problem_id.clone(), x.language.clone()),
+            x,
+        );
+    }
+    Ok(latest
+        .into_values()
+        .map(|x| Submission {
+            record_id: uuid!(x.id),
+            user_id: uuid!(x.user_id),
+            problem_id: uuid!(x.problem_id),
+            language: x.language,
+            code: x.code,
+        })
+        .collect())
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct SimilarityReadPayload {
+    pub id: Uuid,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SimilarityReadFeedback {
+    pub time: DateTime,
+    pub contest_id: Uuid,
+    pub problem_id: Option<Uuid>,
+    pub status: String,
+    pub report: Report,
+}
+
+pub async fn similarity_read(
+    Params(payload): Params<SimilarityReadPayload>,
+    Extension(ref conn): Extension<DatabaseConnection>,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    let model = similarity::Entity::find_by_id(payload.id.to_string())
+        .one(conn)
+        .await?
+        .found()?;
+    principal
+        .check(conn, uuid!(model.contest_id), Action::Similarity)
+        .await?;
+    Ok(Json(SimilarityReadFeedback {
+        time: model.time,
+        contest_id: uuid!(model.contest_id),
+        problem_id: model.problem_id.map(|x| uuid!(x)),
+        status: model.status,
+        report: serde_json::from_str(&model.report).unwrap_or_default(),
+    }))
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct SimilarityListPayload {
+    pub contest_id: Uuid,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SimilarityModel {
+    pub id: Uuid,
+    pub time: DateTime,
+    pub user_id: Uuid,
+    pub problem_id: Option<Uuid>,
+    pub status: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SimilarityListFeedback {
+    pub reports: Vec<SimilarityModel>,
+}
+
+pub async fn similarity_list(
+    Params(payload): Params<SimilarityListPayload>,
+    Extension(ref conn): Extension<DatabaseConnection>,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    principal
+        .check(conn, payload.contest_id, Action::Similarity)
+        .await?;
+    let reports = similarity::Entity::find()
+        .filter(similarity::Column::ContestId.eq(payload.contest_id.to_string()))
+        .order_by_desc(similarity::Column::Time)
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|x| SimilarityModel {
+            id: uuid!(x.id),
+            time: x.time,
+            user_id: uuid!(x.user_id),
+            problem_id: x.problem_id.map(|x| uuid!(x)),
+            status: x.status,
+        })
+        .collect();
+    Ok(Json(SimilarityListFeedback { reports }))
+}
@@ -1,69 +1,260 @@
-use super::auth;
-use crate::judger::Judger;
+use super::auth::{Action, Principal};
+use super::testdata::commit;
+use crate::judger::{output_dir, Judge, Judger, Kind};
+use crate::package::{self, Bound};
 use crate::sys::schema::*;
 use crate::web::prelude::*;
+use axum::body::{Body, Bytes};
+use axum::extract::{FromRequest, Multipart, RequestParts};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::Request;
 use axum::response::IntoResponse;
 use axum::{Extension, Json};
 use chrono::Utc;
+use schemars::JsonSchema;
 use sea_orm::prelude::*;
 use sea_orm::Set;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::str::FromStr;
-use tower_cookies::Cookies;
 use uuid::Uuid;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct SubmitPayload {
     pub problem_id: Uuid,
     pub code: String,
     pub language: String,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, JsonSchema)]
+pub struct SubmitOutputPayload {
+    pub problem_id: Uuid,
+}
+
+#[derive(Serialize, JsonSchema)]
 pub struct SubmitFeedback {
     pub id: Uuid,
 }
 
 pub async fn submit(
-    Json(payload): Json<SubmitPayload>,
     Extension(conn): Extension<DatabaseConnection>,
     Extension(judger): Extension<Judger>,
-    cookies: Cookies,
+    principal: Principal,
+    request: Request<Body>,
 ) -> AppResult<impl IntoResponse> {
+    check_draining(&judger)?;
+    let multipart = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .map(|x| x.starts_with("multipart/form-data"))
+        .unwrap_or(false);
+    let mut request = RequestParts::new(request);
+    let (payload, outputs) = if multipart {
+        let multipart = Multipart::from_request(&mut request).await.good()?;
+        let (problem_id, outputs) = read_outputs(multipart).await?;
+        let payload = SubmitPayload {
+            problem_id,
+            code: String::new(),
+            language: "output".to_string(),
+        };
+        (payload, Some(outputs))
+    } else {
+        let Json(payload) = Json::<SubmitPayload>::from_request(&mut request)
+            .await
+            .good()?;
+        (payload, None)
+    };
     let model = problem::Entity::find_by_id(payload.problem_id.to_string())
         .one(&conn)
         .await?
         .found()?;
-    let user_id =
-        auth::check_root_or_admin_of_contest(uuid!(model.contest_id), &conn, &cookies).await?;
+    principal
+        .check(&conn, uuid!(model.contest_id), Action::Submit)
+        .await?;
+    let user_id = principal.id();
+    let kind = Kind::from_str(&model.kind).unwrap_or_default();
+    if (kind == Kind::OutputOnly) != outputs.is_some() {
+        return Err(AppError::BadRequest(None));
+    }
+    let outputs = match outputs {
+        Some(files) => Some(
+            tokio::task::spawn_blocking(move || unpack_outputs(files))
+                .await
+                .map_err(std::io::Error::other)??,
+        ),
+        None => None,
+    };
+    let testdata = if model.testdata.is_empty() {
+        commit(&conn, model.clone()).await?
+    } else {
+        model.testdata.clone()
+    };
     let record_id = Uuid::new_v4();
-    let modell = record::ActiveModel {
-        id: Set(record_id.to_string()),
-        time: Set(Utc::now().naive_local()),
-        user_id: Set(user_id.to_string()),
-        problem_id: Set(payload.problem_id.to_string()),
-        code: Set(payload.code.clone()),
-        language: Set(payload.language.clone()),
-        result: Set("{}".to_string()),
-        status: Set("waiting".to_string()),
+    let result = async {
+        if let Some(outputs) = outputs {
+            let dir = output_dir(record_id);
+            tokio::fs::create_dir_all(&dir).await?;
+            for (name, data) in outputs {
+                tokio::fs::write(format!("{}/{}", dir, name), data).await?;
+            }
+        }
+        let modell = record::ActiveModel {
+            id: Set(record_id.to_string()),
+            time: Set(Utc::now().naive_local()),
+            user_id: Set(user_id.to_string()),
+            problem_id: Set(payload.problem_id.to_string()),
+            code: Set(payload.code.clone()),
+            language: Set(payload.language.clone()),
+            result: Set("{}".to_string()),
+            status: Set("waiting".to_string()),
+            testdata: Set(testdata.clone()),
+        }
+        .insert(&conn)
+        .await?;
+        Ok::<_, AppError>(modell)
     }
-    .insert(&conn)
-    .await?;
-    tokio::spawn(async move {
-        let (status, result) = judger
-            .judge(crate::judger::Judge {
-                record_id,
-                problem_id: payload.problem_id,
-                time_limit: model.limit_time,
-                memory_limit: model.limit_memory,
-                language: payload.language,
-                code: payload.code,
-            })
-            .await;
+    .await;
+    let modell = match result {
+        Ok(x) => x,
+        Err(e) => {
+            let _ = tokio::fs::remove_dir_all(output_dir(record_id)).await;
+            return Err(e);
+        }
+    };
+    let judge = judge_of(record_id, testdata, model, payload.language, payload.code);
+    dispatch(conn, judger, modell, judge);
+    Ok(Json(SubmitFeedback { id: record_id }))
+}
+
+pub fn judge_of(
+    record_id: Uuid,
+    testdata: String,
+    model_problem: problem::Model,
+    language: String,
+    code: String,
+) -> Judge {
+    Judge {
+        record_id,
+        problem_id: uuid!(model_problem.id),
+        testdata,
+        kind: Kind::from_str(&model_problem.kind).unwrap_or_default(),
+        time_limit: model_problem.limit_time as u32,
+        memory_limit: model_problem.limit_memory as u32,
+        input_file: model_problem.input_file,
+        output_file: model_problem.output_file,
+        language,
+        code,
+    }
+}
+
+pub fn check_draining(judger: &Judger) -> AppResult<()> {
+    if judger.draining() {
+        return Err(AppError::Unavailable(Some(anyhow::anyhow!(
+            "the judger is shutting down"
+        ))));
+    }
+    Ok(())
+}
+
+// a judge dropped by a draining judger leaves the record `waiting` for `resume`
+pub fn dispatch(conn: DatabaseConnection, judger: Judger, modell: record::Model, judge: Judge) {
+    judger.clone().spawn(async move {
+        let (status, result) = match judger.judge(judge).await {
+            Some(x) => x,
+            None => return,
+        };
         let mut modell: record::ActiveModel = modell.into();
         modell.status = Set(Into::<&'static str>::into(status).to_string());
         modell.result = Set(serde_json::to_string(&result).unwrap());
         modell.save(&conn).await.unwrap();
     });
-    Ok(Json(SubmitFeedback { id: record_id }))
+}
+
+// requeues the records left `waiting` by the last shutdown
+pub async fn resume(conn: &DatabaseConnection, judger: &Judger) -> AppResult<usize> {
+    let models = record::Entity::find()
+        .filter(record::Column::Status.eq("waiting"))
+        .find_also_related(problem::Entity)
+        .all(conn)
+        .await?;
+    let mut count = 0;
+    for (model, model_problem) in models {
+        let model_problem = match model_problem {
+            Some(x) => x,
+            None => continue,
+        };
+        let judge = judge_of(
+            uuid!(model.id),
+            model.testdata.clone(),
+            model_problem,
+            model.language.clone(),
+            model.code.clone(),
+        );
+        dispatch(conn.clone(), judger.clone(), model, judge);
+        count += 1;
+    }
+    Ok(count)
+}
+
+// the files are kept as uploaded, they are unpacked once the principal may submit
+async fn read_outputs(mut multipart: Multipart) -> AppResult<(Uuid, Vec<(String, Bytes)>)> {
+    let mut payload: Option<SubmitOutputPayload> = None;
+    let mut files = vec![];
+    while let Some(field) = multipart.next_field().await.good()? {
+        let name = field.name().good()?.to_string();
+        if name == "payload" {
+            let data = field.bytes().await.good()?;
+            let s = std::str::from_utf8(&data).good()?;
+            payload = Some(serde_json::from_str(s).good()?);
+        } else if name == "file" {
+            let file_name = field.file_name().good()?.to_string();
+            files.push((file_name, field.bytes().await.good()?));
+        }
+    }
+    Ok((payload.found()?.problem_id, files))
+}
+
+fn unpack_outputs(files: Vec<(String, Bytes)>) -> AppResult<BTreeMap<String, Vec<u8>>> {
+    let mut bound = Bound::default();
+    let mut outputs = BTreeMap::new();
+    for (file_name, data) in files {
+        let entries = if file_name.ends_with(".zip") {
+            package::unzip_bounded(&data, &mut bound)
+                .good()?
+                .into_iter()
+                .map(|(name, data)| {
+                    (
+                        name.rsplit('/').next().unwrap_or_default().to_string(),
+                        data,
+                    )
+                })
+                .collect()
+        } else {
+            bound.entry().good()?;
+            vec![(file_name, bound.read(data.as_ref()).good()?)]
+        };
+        for (name, data) in entries {
+            let name = check_output_name(name)?;
+            if outputs.contains_key(&name) {
+                return Err(AppError::BadRequest(Some(anyhow::anyhow!(
+                    "`{}` is submitted twice",
+                    name
+                ))));
+            }
+            outputs.insert(name, data);
+        }
+    }
+    Ok(outputs)
+}
+
+pub fn check_output_name(name: String) -> AppResult<String> {
+    if name.ends_with(".out") && !name.starts_with('.') && !name.contains(['/', '\\']) {
+        Ok(name)
+    } else {
+        Err(AppError::BadRequest(Some(anyhow::anyhow!(
+            "`{}` is not named after a test",
+            name
+        ))))
+    }
 }
@@ -1,88 +1,187 @@
-use super::auth;
+use super::audit;
+use super::auth::{Action, Principal};
 use crate::config;
+use crate::judger::lint::{is_testdata_name, lint, lint_text, normalize, unpaired, Lint};
+use crate::judger::testdata_dir;
+use crate::judger::version::{restore, snapshot, versions};
+use crate::package::Bound;
 use crate::sys::schema::*;
+use crate::web::params::Params;
 use crate::web::prelude::*;
+use axum::body::{Bytes, StreamBody};
 use axum::extract::Multipart;
+use axum::headers::LastModified;
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
 use axum::response::IntoResponse;
-use axum::{Extension, Json};
+use axum::{Extension, Json, TypedHeader};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use schemars::JsonSchema;
 use sea_orm::prelude::*;
+use sea_orm::{ConnectionTrait, Set, TransactionTrait};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::ffi::CString;
+use std::io::Write;
+use std::path::{Component, Path};
 use std::str::FromStr;
-use tower_cookies::Cookies;
+use std::sync::Mutex;
+use tokio::sync::mpsc::{channel, Sender};
 use uuid::Uuid;
 
-#[derive(Deserialize)]
+// every `file` field is named by its file name, `name` overrides it when there is only one
+#[derive(Deserialize, JsonSchema)]
 pub struct TestdataUploadPayload {
     pub id: Uuid,
-    pub name: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub normalize: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct TestdataUploadFeedback {
+    pub lints: Vec<String>,
 }
 
 pub async fn testdata_upload(
     mut multipart: Multipart,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
     let mut payload: Option<TestdataUploadPayload> = None;
-    let mut file: Option<Vec<u8>> = None;
-    while let Some(field) = multipart.next_field().await.unwrap() {
+    let mut files: Vec<(Option<String>, Vec<u8>)> = vec![];
+    while let Some(field) = multipart.next_field().await.good()? {
         let name = field.name().good()?.to_string();
         if name == "payload" {
             let data = field.bytes().await.good()?;
             let s = std::str::from_utf8(&data).good()?;
             payload = Some(serde_json::from_str(s).good()?);
         } else if name == "file" {
-            file = Some(field.bytes().await.good()?.to_vec());
+            let file_name = field.file_name().map(|x| x.to_string());
+            files.push((file_name, field.bytes().await.good()?.to_vec()));
         }
     }
     let payload = payload.found()?;
-    let file = file.found()?;
+    if files.is_empty() {
+        return Err(AppError::NotFound(None));
+    }
+    if payload.name.is_some() && files.len() > 1 {
+        return Err(AppError::BadRequest(Some(anyhow::anyhow!(
+            "`name` can only be given with a single file"
+        ))));
+    }
     let model = problem::Entity::find_by_id(payload.id.to_string())
         .one(conn)
         .await?
         .found()?;
-    auth::check_root_or_admin_of_contest(uuid!(model.contest_id), conn, &cookies).await?;
-    tokio::fs::write(
-        format!(
-            "{}/{}/{}/{}",
-            config().judger.root,
-            "testdata",
-            payload.id,
-            payload.name
-        ),
-        file,
+    principal
+        .check(conn, uuid!(model.contest_id), Action::Edit)
+        .await?;
+    let mut names = existing(payload.id).await?;
+    let mut lints = vec![];
+    let mut prepared = vec![];
+    for (file_name, file) in files {
+        let name = payload.name.clone().or(file_name).good()?;
+        let (file, x) = prepare(&name, file, payload.normalize)?;
+        names.insert(name.clone());
+        lints.extend(x);
+        prepared.push((name, file));
+    }
+    let uploaded = prepared.iter().map(|x| x.0.clone()).collect();
+    check_paired(&names, &uploaded)?;
+    let names = prepared.iter().map(|x| x.0.clone()).collect::<Vec<_>>();
+    tokio::task::spawn_blocking(move || update(payload.id, prepared))
+        .await
+        .map_err(std::io::Error::other)??;
+    let before = json!({ "testdata": model.testdata });
+    let txn = conn.begin().await?;
+    let testdata = commit(&txn, model).await?;
+    audit::log(
+        &txn,
+        &principal,
+        "testdata_upload",
+        payload.id,
+        Some(&before),
+        Some(&json!({ "testdata": testdata, "names": names })),
     )
     .await?;
-    Ok(Json(()))
+    txn.commit().await?;
+    let lints = lints
+        .into_iter()
+        .map(|x| format!("`{}`: {}", x.name, x.message))
+        .collect();
+    Ok(Json(TestdataUploadFeedback { lints }))
 }
 
-#[derive(Deserialize)]
+// the names in the testdata directory of a problem
+async fn existing(problem_id: Uuid) -> AppResult<BTreeSet<String>> {
+    let mut names = BTreeSet::new();
+    if let Ok(mut dir) = tokio::fs::read_dir(testdata_dir(problem_id)).await {
+        while let Some(entry) = dir.next_entry().await? {
+            if let Ok(name) = entry.file_name().into_string() {
+                names.insert(name);
+            }
+        }
+    }
+    Ok(names)
+}
+
+// tests are uploaded as pairs, the judger would skip a lone input or answer; files already
+// lone in `names` are left to the lints, so that they do not block every later upload
+fn check_paired(names: &BTreeSet<String>, uploaded: &BTreeSet<String>) -> AppResult<()> {
+    let lints = unpaired(names)
+        .into_iter()
+        .filter(|x| uploaded.contains(&x.name))
+        .collect::<Vec<_>>();
+    if lints.is_empty() {
+        return Ok(());
+    }
+    Err(AppError::BadRequest(Some(anyhow::anyhow!(
+        "{}",
+        lints
+            .iter()
+            .map(|x| format!("`{}`: {}", x.name, x.message))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))))
+}
+
+#[derive(Deserialize, JsonSchema)]
 pub struct TestdataDownloadPayload {
     pub id: Uuid,
     pub name: String,
 }
 
 pub async fn testdata_download(
-    Json(payload): Json<TestdataDownloadPayload>,
+    Params(payload): Params<TestdataDownloadPayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
     let model = problem::Entity::find_by_id(payload.id.to_string())
         .one(conn)
         .await?
         .found()?;
-    auth::check_root_or_admin_or_player_of_contest(uuid!(model.contest_id), conn, &cookies).await?;
-    let data = tokio::fs::read(format!(
+    principal
+        .check(conn, uuid!(model.contest_id), Action::Edit)
+        .await?;
+    if !is_testdata_name(&payload.name) {
+        return Err(AppError::BadRequest(None));
+    }
+    let path = format!(
         "{}/{}/{}/{}",
         config().judger.root,
         "testdata",
         payload.id,
         payload.name
-    ))
-    .await?;
-    Ok(Json(data))
+    );
+    let modified = tokio::fs::metadata(&path).await?.modified()?;
+    let data = tokio::fs::read(&path).await?;
+    Ok((TypedHeader(LastModified::from(modified)), Json(data)))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct TestdataDeletePayload {
     pub id: Uuid,
     pub name: String,
@@ -91,13 +190,18 @@ pub struct TestdataDeletePayload {
 pub async fn testdata_delete(
     Json(payload): Json<TestdataDeletePayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
     let model = problem::Entity::find_by_id(payload.id.to_string())
         .one(conn)
         .await?
         .found()?;
-    auth::check_root_or_admin_or_player_of_contest(uuid!(model.contest_id), conn, &cookies).await?;
+    principal
+        .check(conn, uuid!(model.contest_id), Action::Edit)
+        .await?;
+    if !is_testdata_name(&payload.name) {
+        return Err(AppError::BadRequest(None));
+    }
     tokio::fs::remove_file(format!(
         "{}/{}/{}/{}",
         config().judger.root,
@@ -106,34 +210,49 @@ pub async fn testdata_delete(
         payload.name
     ))
     .await?;
+    let before = json!({ "testdata": model.testdata });
+    let txn = conn.begin().await?;
+    let testdata = commit(&txn, model).await?;
+    audit::log(
+        &txn,
+        &principal,
+        "testdata_delete",
+        payload.id,
+        Some(&before),
+        Some(&json!({ "testdata": testdata, "name": payload.name })),
+    )
+    .await?;
+    txn.commit().await?;
     Ok(Json(()))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct TestdataListPayload {
     pub id: Uuid,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct TestdataModel {
     pub name: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct TestdataListFeedback {
     pub testdata: Vec<TestdataModel>,
 }
 
 pub async fn testdata_list(
-    Json(payload): Json<TestdataListPayload>,
+    Params(payload): Params<TestdataListPayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
     let model = problem::Entity::find_by_id(payload.id.to_string())
         .one(conn)
         .await?
         .found()?;
-    auth::check_root_or_admin_or_player_of_contest(uuid!(model.contest_id), conn, &cookies).await?;
+    principal
+        .check(conn, uuid!(model.contest_id), Action::View)
+        .await?;
     if let Ok(mut dir) = tokio::fs::read_dir(format!(
         "{}/{}/{}",
         config().judger.root,
@@ -153,3 +272,424 @@ pub async fn testdata_list(
         Ok(Json(TestdataListFeedback { testdata: vec![] }))
     }
 }
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TestdataCheckPayload {
+    pub id: Uuid,
+}
+
+pub async fn testdata_check(
+    Params(payload): Params<TestdataCheckPayload>,
+    Extension(ref conn): Extension<DatabaseConnection>,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    let model = problem::Entity::find_by_id(payload.id.to_string())
+        .one(conn)
+        .await?
+        .found()?;
+    principal
+        .check(conn, uuid!(model.contest_id), Action::Edit)
+        .await?;
+    let health = tokio::task::spawn_blocking(move || lint(payload.id))
+        .await
+        .map_err(std::io::Error::other)?
+        .map_err(std::io::Error::other)?;
+    Ok(Json(health))
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TestdataArchiveUploadPayload {
+    pub id: Uuid,
+    #[serde(default)]
+    pub normalize: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct TestdataArchiveUploadFeedback {
+    pub names: Vec<String>,
+    pub lints: Vec<Lint>,
+}
+
+pub async fn testdata_archive_upload(
+    mut multipart: Multipart,
+    Extension(ref conn): Extension<DatabaseConnection>,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    let mut payload: Option<TestdataArchiveUploadPayload> = None;
+    let mut file: Option<(String, Vec<u8>)> = None;
+    while let Some(field) = multipart.next_field().await.good()? {
+        let name = field.name().good()?.to_string();
+        if name == "payload" {
+            let data = field.bytes().await.good()?;
+            let s = std::str::from_utf8(&data).good()?;
+            payload = Some(serde_json::from_str(s).good()?);
+        } else if name == "file" {
+            let file_name = field.file_name().good()?.to_string();
+            file = Some((file_name, field.bytes().await.good()?.to_vec()));
+        }
+    }
+    let payload = payload.found()?;
+    let (file_name, file) = file.found()?;
+    let model = problem::Entity::find_by_id(payload.id.to_string())
+        .one(conn)
+        .await?
+        .found()?;
+    principal
+        .check(conn, uuid!(model.contest_id), Action::Edit)
+        .await?;
+    let entries = tokio::task::spawn_blocking(move || unpack(&file_name, file))
+        .await
+        .map_err(std::io::Error::other)??;
+    let mut names = vec![];
+    let mut lints = vec![];
+    let mut files = vec![];
+    for (name, data) in entries {
+        if names.contains(&name) {
+            return Err(AppError::BadRequest(Some(anyhow::anyhow!(
+                "`{}` appears twice in the archive",
+                name
+            ))));
+        }
+        let (data, x) = prepare(&name, data, payload.normalize)?;
+        names.push(name.clone());
+        lints.extend(x);
+        files.push((name, data));
+    }
+    let uploaded = names.iter().cloned().collect();
+    check_paired(&uploaded, &uploaded)?;
+    tokio::task::spawn_blocking(move || replace(payload.id, files))
+        .await
+        .map_err(std::io::Error::other)??;
+    let before = json!({ "testdata": model.testdata });
+    let txn = conn.begin().await?;
+    let testdata = commit(&txn, model).await?;
+    audit::log(
+        &txn,
+        &principal,
+        "testdata_archive_upload",
+        payload.id,
+        Some(&before),
+        Some(&json!({ "testdata": testdata, "names": names })),
+    )
+    .await?;
+    txn.commit().await?;
+    names.sort();
+    Ok(Json(TestdataArchiveUploadFeedback { names, lints }))
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TestdataArchiveDownloadPayload {
+    pub id: Uuid,
+}
+
+pub async fn testdata_archive_download(
+    Params(payload): Params<TestdataArchiveDownloadPayload>,
+    Extension(ref conn): Extension<DatabaseConnection>,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    let model = problem::Entity::find_by_id(payload.id.to_string())
+        .one(conn)
+        .await?
+        .found()?;
+    principal
+        .check(conn, uuid!(model.contest_id), Action::Edit)
+        .await?;
+    let (tx, rx) = channel::<std::io::Result<Bytes>>(16);
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = pack(payload.id, ChannelWriter(tx.clone())) {
+            let _ = tx.blocking_send(Err(e));
+        }
+    });
+    let stream =
+        futures::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|x| (x, rx)) });
+    Ok((
+        [
+            (CONTENT_TYPE, "application/gzip".to_string()),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.tar.gz\"", payload.id),
+            ),
+        ],
+        StreamBody::new(stream),
+    ))
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TestdataVersionPayload {
+    pub id: Uuid,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct TestdataVersionModel {
+    pub hash: String,
+    pub time: DateTime,
+    pub names: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct TestdataVersionFeedback {
+    pub current: String,
+    pub versions: Vec<TestdataVersionModel>,
+}
+
+pub async fn testdata_version(
+    Params(payload): Params<TestdataVersionPayload>,
+    Extension(ref conn): Extension<DatabaseConnection>,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    let model = problem::Entity::find_by_id(payload.id.to_string())
+        .one(conn)
+        .await?
+        .found()?;
+    principal
+        .check(conn, uuid!(model.contest_id), Action::Edit)
+        .await?;
+    let versions = tokio::task::spawn_blocking(move || versions(payload.id))
+        .await
+        .map_err(std::io::Error::other)?
+        .map_err(std::io::Error::other)?
+        .into_iter()
+        .map(|x| TestdataVersionModel {
+            hash: x.hash,
+            time: x.time,
+            names: x.files.into_keys().collect(),
+        })
+        .collect();
+    Ok(Json(TestdataVersionFeedback {
+        current: model.testdata,
+        versions,
+    }))
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TestdataRollbackPayload {
+    pub id: Uuid,
+    pub version: String,
+}
+
+pub async fn testdata_rollback(
+    Json(payload): Json<TestdataRollbackPayload>,
+    Extension(ref conn): Extension<DatabaseConnection>,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    let model = problem::Entity::find_by_id(payload.id.to_string())
+        .one(conn)
+        .await?
+        .found()?;
+    principal
+        .check(conn, uuid!(model.contest_id), Action::Edit)
+        .await?;
+    let version = payload.version.clone();
+    let files = tokio::task::spawn_blocking(move || restore(payload.id, &version))
+        .await
+        .map_err(std::io::Error::other)?
+        .found()?;
+    tokio::task::spawn_blocking(move || replace(payload.id, files))
+        .await
+        .map_err(std::io::Error::other)??;
+    let before = json!({ "testdata": model.testdata });
+    let txn = conn.begin().await?;
+    let testdata = commit(&txn, model).await?;
+    audit::log(
+        &txn,
+        &principal,
+        "testdata_rollback",
+        payload.id,
+        Some(&before),
+        Some(&json!({ "testdata": testdata })),
+    )
+    .await?;
+    txn.commit().await?;
+    Ok(Json(()))
+}
+
+pub async fn commit<C: ConnectionTrait>(conn: &C, model: problem::Model) -> AppResult<String> {
+    let problem_id = uuid!(model.id);
+    let hash = tokio::task::spawn_blocking(move || snapshot(problem_id))
+        .await
+        .map_err(std::io::Error::other)?
+        .map_err(std::io::Error::other)?;
+    let mut model: problem::ActiveModel = model.into();
+    model.testdata = Set(hash.clone());
+    model.update(conn).await?;
+    Ok(hash)
+}
+
+pub fn prepare(name: &str, mut file: Vec<u8>, normalized: bool) -> AppResult<(Vec<u8>, Vec<Lint>)> {
+    if !is_testdata_name(name) {
+        return Err(AppError::BadRequest(Some(anyhow::anyhow!(
+            "`{}` is neither a test file nor a known helper",
+            name
+        ))));
+    }
+    let mut lints = vec![];
+    if name.ends_with(".in") || name.ends_with(".out") {
+        if std::str::from_utf8(&file).is_err() {
+            return Err(AppError::BadRequest(Some(anyhow::anyhow!(
+                "`{}` is not valid UTF-8",
+                name
+            ))));
+        }
+        if normalized {
+            file = normalize(&file);
+        }
+        lints = lint_text(&file)
+            .into_iter()
+            .map(|x| Lint {
+                name: name.to_string(),
+                message: x.to_string(),
+            })
+            .collect();
+    }
+    Ok((file, lints))
+}
+
+fn entry_name(path: &Path) -> AppResult<Option<String>> {
+    if path
+        .components()
+        .any(|x| !matches!(x, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(AppError::BadRequest(Some(anyhow::anyhow!(
+            "`{}` escapes the testdata directory",
+            path.display()
+        ))));
+    }
+    if path.components().any(|x| {
+        let x = x.as_os_str().to_string_lossy();
+        x.starts_with('.') || x == "__MACOSX"
+    }) {
+        return Ok(None);
+    }
+    // archives made by hand often wrap everything in one directory
+    let name = path.file_name().and_then(|x| x.to_str()).good()?;
+    Ok(Some(name.to_string()))
+}
+
+fn unpack(file_name: &str, file: Vec<u8>) -> AppResult<Vec<(String, Vec<u8>)>> {
+    let mut entries = vec![];
+    let mut bound = Bound::default();
+    if file_name.ends_with(".zip") {
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(file)).good()?;
+        for i in 0..archive.len() {
+            bound.entry().good()?;
+            let entry = archive.by_index(i).good()?;
+            if entry.is_dir() {
+                continue;
+            }
+            if let Some(name) = entry_name(Path::new(entry.name()))? {
+                entries.push((name, bound.read(entry).good()?));
+            }
+        }
+    } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+        let mut archive = tar::Archive::new(GzDecoder::new(file.as_slice()));
+        for entry in archive.entries()? {
+            bound.entry().good()?;
+            let entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            if let Some(name) = entry_name(&entry.path()?)? {
+                entries.push((name, bound.read(entry).good()?));
+            }
+        }
+    } else {
+        return Err(AppError::BadRequest(Some(anyhow::anyhow!(
+            "`{}` is neither a zip nor a tar.gz archive",
+            file_name
+        ))));
+    }
+    Ok(entries)
+}
+
+// held while a testdata directory is staged and swapped, so that no update is lost
+static STAGING: Mutex<()> = Mutex::new(());
+
+pub fn replace(problem_id: Uuid, files: Vec<(String, Vec<u8>)>) -> std::io::Result<()> {
+    let _guard = STAGING.lock().unwrap_or_else(|x| x.into_inner());
+    stage(problem_id, files, false)
+}
+
+// like `replace`, but the files not uploaded again are kept
+pub fn update(problem_id: Uuid, files: Vec<(String, Vec<u8>)>) -> std::io::Result<()> {
+    let _guard = STAGING.lock().unwrap_or_else(|x| x.into_inner());
+    stage(problem_id, files, true)
+}
+
+// the new testdata is built beside the old one and swapped in, judges never see half of it
+fn stage(problem_id: Uuid, files: Vec<(String, Vec<u8>)>, keep: bool) -> std::io::Result<()> {
+    let dir = testdata_dir(problem_id);
+    let staging = format!("{}.{}", dir, Uuid::new_v4());
+    let result = (|| {
+        std::fs::create_dir_all(&staging)?;
+        if keep && Path::new(&dir).exists() {
+            for entry in std::fs::read_dir(&dir)? {
+                let entry = entry?;
+                let name = entry.file_name();
+                if entry.file_type()?.is_file() && !files.iter().any(|x| name == x.0.as_str()) {
+                    std::fs::hard_link(entry.path(), Path::new(&staging).join(name))?;
+                }
+            }
+        }
+        for (name, data) in files {
+            std::fs::write(format!("{}/{}", staging, name), data)?;
+        }
+        swap(&dir, &staging)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_dir_all(&staging);
+    }
+    result
+}
+
+fn swap(dir: &str, staging: &str) -> std::io::Result<()> {
+    if Path::new(dir).exists() {
+        let from = CString::new(staging).map_err(std::io::Error::other)?;
+        let to = CString::new(dir).map_err(std::io::Error::other)?;
+        let code = unsafe {
+            libc::renameat2(
+                libc::AT_FDCWD,
+                from.as_ptr(),
+                libc::AT_FDCWD,
+                to.as_ptr(),
+                libc::RENAME_EXCHANGE,
+            )
+        };
+        if code != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // after the exchange the staging directory holds the old testdata
+        std::fs::remove_dir_all(staging)?;
+    } else {
+        std::fs::rename(staging, dir)?;
+    }
+    Ok(())
+}
+
+fn pack(problem_id: Uuid, writer: ChannelWriter) -> std::io::Result<()> {
+    let dir = testdata_dir(problem_id);
+    let mut names = std::fs::read_dir(&dir)?
+        .filter_map(|x| x.ok())
+        .filter(|x| x.file_type().map(|x| x.is_file()).unwrap_or(false))
+        .filter_map(|x| x.file_name().into_string().ok())
+        .collect::<Vec<_>>();
+    names.sort();
+    let mut builder = tar::Builder::new(GzEncoder::new(writer, flate2::Compression::default()));
+    for name in names {
+        builder.append_path_with_name(format!("{}/{}", dir, name), name)?;
+    }
+    builder.into_inner()?.finish()?.flush()
+}
+
+struct ChannelWriter(Sender<std::io::Result<Bytes>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+use super::auth::Principal;
+use super::contest::{contest_ranklist, ContestRanklistPayload};
+use super::record::{record_list, record_read, RecordReadPayload};
+use crate::sys::schema::*;
+use crate::web::list::ListQuery;
+use crate::web::params::Params;
+use crate::web::prelude::*;
+use axum::body::HttpBody;
+use axum::response::IntoResponse;
+use axum::Extension;
+use chrono::NaiveDate;
+use sea_orm::prelude::*;
+use sea_orm::{ConnectOptions, Database, Set};
+use serde_json::Value;
+use uuid::Uuid;
+
+// one connection, every connection to `sqlite::memory:` opens a database of its own
+async fn database() -> DatabaseConnection {
+    let mut options = ConnectOptions::new("sqlite::memory:".to_string());
+    options.max_connections(1);
+    let conn = Database::connect(options).await.unwrap();
+    crate::migration::up(&conn, None).await.unwrap();
+    conn
+}
+
+fn at(hour: u32) -> DateTime {
+    NaiveDate::from_ymd_opt(2022, 6, 1)
+        .and_then(|x| x.and_hms_opt(hour, 0, 0))
+        .unwrap()
+}
+
+async fn user(conn: &DatabaseConnection, name: &str) -> user::Model {
+    user::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        name: Set(name.to_string()),
+        nick: Set(name.to_string()),
+        description: Set(String::new()),
+        password: Set(vec![]),
+        banned: Set(false),
+        root: Set(false),
+    }
+    .insert(conn)
+    .await
+    .unwrap()
+}
+
+fn principal(user: &user::Model) -> Principal {
+    Principal {
+        user: user.clone(),
+        scope: None,
+    }
+}
+
+async fn privilege(conn: &DatabaseConnection, user: &user::Model, contest_id: Uuid, kind: &str) {
+    privilege::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        user_id: Set(user.id.clone()),
+        contest_id: Set(contest_id.to_string()),
+        kind: Set(kind.to_string()),
+    }
+    .insert(conn)
+    .await
+    .unwrap();
+}
+
+async fn contest(conn: &DatabaseConnection, freeze_time: Option<DateTime>) -> (Uuid, Uuid) {
+    let contest_id = Uuid::new_v4();
+    contest::ActiveModel {
+        id: Set(contest_id.to_string()),
+        nick: Set(contest_id.to_string()),
+        description: Set(String::new()),
+        start: Set(at(9)),
+        end: Set(at(14)),
+        freeze_time: Set(freeze_time),
+    }
+    .insert(conn)
+    .await
+    .unwrap();
+    let problem_id = Uuid::new_v4();
+    problem::ActiveModel {
+        id: Set(problem_id.to_string()),
+        nick: Set(problem_id.to_string()),
+        description: Set(String::new()),
+        limit_time: Set(1000),
+        limit_memory: Set(256),
+        contest_id: Set(contest_id.to_string()),
+        kind: Set("traditional".to_string()),
+        input_file: Set(None),
+        output_file: Set(None),
+        testdata: Set(String::new()),
+    }
+    .insert(conn)
+    .await
+    .unwrap();
+    (contest_id, problem_id)
+}
+
+async fn record(
+    conn: &DatabaseConnection,
+    user: &user::Model,
+    problem_id: Uuid,
+    time: DateTime,
+    status: &str,
+) -> Uuid {
+    let record_id = Uuid::new_v4();
+    record::ActiveModel {
+        id: Set(record_id.to_string()),
+        time: Set(time),
+        user_id: Set(user.id.clone()),
+        problem_id: Set(problem_id.to_string()),
+        code: Set(String::new()),
+        language: Set("c".to_string()),
+        result: Set("{}".to_string()),
+        status: Set(status.to_string()),
+        testdata: Set(String::new()),
+    }
+    .insert(conn)
+    .await
+    .unwrap();
+    record_id
+}
+
+async fn json(result: AppResult<impl IntoResponse>) -> Value {
+    let mut body = result.unwrap().into_response().into_body();
+    let mut data = vec![];
+    while let Some(chunk) = body.data().await {
+        data.extend_from_slice(&chunk.unwrap());
+    }
+    serde_json::from_slice(&data).unwrap()
+}
+
+async fn listed(conn: &DatabaseConnection, user: &user::Model) -> Vec<String> {
+    let feedback = json(
+        record_list(
+            ListQuery::default(),
+            Extension(conn.clone()),
+            principal(user),
+        )
+        .await,
+    )
+    .await;
+    let mut ids = feedback["records"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|x| x["id"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    ids.sort();
+    ids
+}
+
+async fn readable(conn: &DatabaseConnection, user: &user::Model, id: Uuid) -> bool {
+    let result = record_read(
+        Params(RecordReadPayload { id }),
+        Extension(conn.clone()),
+        principal(user),
+    )
+    .await;
+    match result {
+        Ok(_) => true,
+        Err(AppError::Forbidden(_)) => false,
+        Err(e) => panic!("{:?}", e),
+    }
+}
+
+fn sorted(ids: &[Uuid]) -> Vec<String> {
+    let mut ids = ids.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+    ids.sort();
+    ids
+}
+
+#[tokio::test]
+async fn observers_do_not_see_records_after_the_freeze() {
+    let conn = database().await;
+    let (contest_id, problem_id) = contest(&conn, Some(at(12))).await;
+    let (open_id, open_problem_id) = contest(&conn, None).await;
+    let player = user(&conn, "player").await;
+    let observer = user(&conn, "observer").await;
+    let judge = user(&conn, "judge").await;
+    privilege(&conn, &player, contest_id, "player").await;
+    privilege(&conn, &observer, contest_id, "observer").await;
+    privilege(&conn, &observer, open_id, "observer").await;
+    privilege(&conn, &judge, contest_id, "observer").await;
+    privilege(&conn, &judge, contest_id, "judge").await;
+    let before = record(&conn, &player, problem_id, at(11), "accepted").await;
+    let after = record(&conn, &player, problem_id, at(13), "accepted").await;
+    let open = record(&conn, &player, open_problem_id, at(13), "accepted").await;
+
+    assert_eq!(listed(&conn, &observer).await, sorted(&[before, open]));
+    assert!(readable(&conn, &observer, before).await);
+    assert!(!readable(&conn, &observer, after).await);
+    assert!(readable(&conn, &observer, open).await);
+
+    // a role that sees every record is not held back by also observing
+    assert_eq!(listed(&conn, &judge).await, sorted(&[before, after]));
+    assert!(readable(&conn, &judge, after).await);
+
+    // contestants still see their own submissions
+    assert_eq!(listed(&conn, &player).await, sorted(&[before, after]));
+    assert!(readable(&conn, &player, after).await);
+}
+
+#[tokio::test]
+async fn ranklist_keeps_the_first_accepted_record() {
+    let conn = database().await;
+    let (contest_id, problem_id) = contest(&conn, None).await;
+    let (_, other_problem_id) = contest(&conn, None).await;
+    let player = user(&conn, "player").await;
+    privilege(&conn, &player, contest_id, "player").await;
+    record(&conn, &player, problem_id, at(10), "wrong_answer").await;
+    let accepted = record(&conn, &player, problem_id, at(11), "accepted").await;
+    record(&conn, &player, problem_id, at(12), "accepted").await;
+    record(&conn, &player, other_problem_id, at(10), "accepted").await;
+
+    let feedback = json(
+        contest_ranklist(
+            Params(ContestRanklistPayload { id: contest_id }),
+            Extension(conn.clone()),
+            principal(&player),
+        )
+        .await,
+    )
+    .await;
+    let records = feedback["records"].as_object().unwrap();
+    assert_eq!(records.len(), 1);
+    let problems = records[&player.id].as_object().unwrap();
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[&problem_id.to_string()][0], accepted.to_string());
+    assert_eq!(problems[&problem_id.to_string()][1], "accepted");
+}
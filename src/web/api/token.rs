@@ -0,0 +1,129 @@
+use super::auth::{Principal, Scope};
+use crate::sys::schema::*;
+use crate::utils::{hex, sha256};
+use crate::web::params::Params;
+use crate::web::prelude::*;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use chrono::Utc;
+use schemars::JsonSchema;
+use sea_orm::prelude::*;
+use sea_orm::{QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TokenCreatePayload {
+    pub name: String,
+    pub scope: Scope,
+    pub expires: Option<DateTime>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct TokenCreateFeedback {
+    pub id: Uuid,
+    pub token: String,
+}
+
+pub async fn token_create(
+    Json(payload): Json<TokenCreatePayload>,
+    Extension(ref conn): Extension<DatabaseConnection>,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    let user_id = principal.check_session()?;
+    // the plain token is returned once, only its hash is kept
+    let token = format!(
+        "bj_{}{}",
+        Uuid::new_v4().to_simple(),
+        Uuid::new_v4().to_simple()
+    );
+    let model = token::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        user_id: Set(user_id.to_string()),
+        name: Set(payload.name),
+        hash: Set(hex(&sha256(&token))),
+        scope: Set(Into::<&'static str>::into(payload.scope).to_string()),
+        created: Set(Utc::now().naive_local()),
+        expires: Set(payload.expires),
+    }
+    .insert(conn)
+    .await?;
+    Ok(Json(TokenCreateFeedback {
+        id: uuid!(model.id),
+        token,
+    }))
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TokenListPayload {
+    pub user_id: Option<Uuid>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct TokenModel {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub scope: Scope,
+    pub created: DateTime,
+    pub expires: Option<DateTime>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct TokenListFeedback {
+    pub tokens: Vec<TokenModel>,
+}
+
+pub async fn token_list(
+    Params(payload): Params<TokenListPayload>,
+    Extension(ref conn): Extension<DatabaseConnection>,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    let user_id = principal.check_session()?;
+    let user_id = match payload.user_id {
+        Some(x) if x != user_id => {
+            principal.check_root()?;
+            x
+        }
+        _ => user_id,
+    };
+    let tokens = token::Entity::find()
+        .filter(token::Column::UserId.eq(user_id.to_string()))
+        .order_by_asc(token::Column::Created)
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|x| TokenModel {
+            id: uuid!(x.id),
+            user_id: uuid!(x.user_id),
+            name: x.name,
+            scope: Scope::from_str(&x.scope).unwrap_or(Scope::ReadOnly),
+            created: x.created,
+            expires: x.expires,
+        })
+        .collect();
+    Ok(Json(TokenListFeedback { tokens }))
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TokenDeletePayload {
+    pub id: Uuid,
+}
+
+pub async fn token_delete(
+    Json(payload): Json<TokenDeletePayload>,
+    Extension(ref conn): Extension<DatabaseConnection>,
+    principal: Principal,
+) -> AppResult<impl IntoResponse> {
+    let user_id = principal.check_session()?;
+    let model = token::Entity::find_by_id(payload.id.to_string())
+        .one(conn)
+        .await?
+        .found()?;
+    if uuid!(model.user_id) != user_id {
+        principal.check_root()?;
+    }
+    model.delete(conn).await?;
+    Ok(Json(()))
+}
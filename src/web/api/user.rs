@@ -1,16 +1,20 @@
-use super::auth;
+use super::audit;
+use super::auth::Principal;
 use crate::sys::schema::*;
 use crate::utils::sha256;
+use crate::web::list::ListQuery;
+use crate::web::params::Params;
 use crate::web::prelude::*;
 use axum::response::IntoResponse;
 use axum::{Extension, Json};
+use schemars::JsonSchema;
 use sea_orm::prelude::*;
-use sea_orm::{QueryOrder, Set};
+use sea_orm::sea_query::Query;
+use sea_orm::{Set, TransactionTrait};
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
-use tower_cookies::Cookies;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct UserCreatePayload {
     pub name: String,
     pub nick: String,
@@ -20,7 +24,7 @@ pub struct UserCreatePayload {
     pub root: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(tag = "type")]
 pub struct UserCreateFeedback {
     pub id: Uuid,
@@ -29,26 +33,37 @@ pub struct UserCreateFeedback {
 pub async fn user_create(
     Json(payload): Json<UserCreatePayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    auth::check_root(conn, &cookies).await?;
+    principal.check_root()?;
+    let txn = conn.begin().await?;
     let model = user::ActiveModel {
         id: Set(Uuid::new_v4().to_string()),
         name: Set(payload.name),
         nick: Set(payload.nick),
         description: Set(payload.description),
         password: Set(sha256(&payload.password)),
-        banned: Set(payload.banned.into()),
-        root: Set(payload.root.into()),
+        banned: Set(payload.banned),
+        root: Set(payload.root),
     }
-    .insert(conn)
+    .insert(&txn)
     .await?;
+    audit::log(
+        &txn,
+        &principal,
+        "user_create",
+        uuid!(model.id),
+        None,
+        Some(&model),
+    )
+    .await?;
+    txn.commit().await?;
     Ok(Json(UserCreateFeedback {
         id: uuid!(model.id),
     }))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct UserUpdatePayload {
     pub id: Uuid,
     pub name: Option<String>,
@@ -62,13 +77,14 @@ pub struct UserUpdatePayload {
 pub async fn user_update(
     Json(payload): Json<UserUpdatePayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    auth::check_root(conn, &cookies).await?;
+    principal.check_root()?;
     let model = user::Entity::find_by_id(payload.id.to_string())
         .one(conn)
         .await?
         .found()?;
+    let before = model.clone();
     let mut model: user::ActiveModel = model.into();
     if let Some(name) = payload.name {
         model.name = Set(name);
@@ -83,21 +99,32 @@ pub async fn user_update(
         model.password = Set(sha256(&password));
     }
     if let Some(banned) = payload.banned {
-        model.banned = Set(banned.into());
+        model.banned = Set(banned);
     }
     if let Some(root) = payload.root {
-        model.root = Set(root.into());
+        model.root = Set(root);
     }
-    model.update(conn).await?;
+    let txn = conn.begin().await?;
+    let model = model.update(&txn).await?;
+    audit::log(
+        &txn,
+        &principal,
+        "user_update",
+        payload.id,
+        Some(&before),
+        Some(&model),
+    )
+    .await?;
+    txn.commit().await?;
     Ok(Json(()))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct UserReadPayload {
     pub id: Uuid,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct UserReadFeedback {
     pub name: String,
     pub nick: String,
@@ -107,11 +134,11 @@ pub struct UserReadFeedback {
 }
 
 pub async fn user_read(
-    Json(payload): Json<UserReadPayload>,
+    Params(payload): Params<UserReadPayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    auth::check_root(conn, &cookies).await?;
+    principal.check_root()?;
     let model = user::Entity::find_by_id(payload.id.to_string())
         .one(conn)
         .await?
@@ -120,12 +147,12 @@ pub async fn user_read(
         name: model.name,
         nick: model.nick,
         description: model.description,
-        banned: model.banned != 0,
-        root: model.root != 0,
+        banned: model.banned,
+        root: model.root,
     }))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct UserDeletePayload {
     pub id: Uuid,
 }
@@ -133,16 +160,29 @@ pub struct UserDeletePayload {
 pub async fn user_delete(
     Json(payload): Json<UserDeletePayload>,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    auth::check_root(conn, &cookies).await?;
-    user::Entity::delete_by_id(payload.id.to_string())
-        .exec(conn)
-        .await?;
+    principal.check_root()?;
+    let model = user::Entity::find_by_id(payload.id.to_string())
+        .one(conn)
+        .await?
+        .found()?;
+    let txn = conn.begin().await?;
+    model.clone().delete(&txn).await?;
+    audit::log(
+        &txn,
+        &principal,
+        "user_delete",
+        payload.id,
+        Some(&model),
+        None,
+    )
+    .await?;
+    txn.commit().await?;
     Ok(Json(()))
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct UserModel {
     pub id: Uuid,
     pub name: String,
@@ -152,20 +192,36 @@ pub struct UserModel {
     pub root: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct UserListFeedback {
     pub users: Vec<UserModel>,
+    pub total: u64,
 }
 
 pub async fn user_list(
+    list: ListQuery,
     Extension(ref conn): Extension<DatabaseConnection>,
-    cookies: Cookies,
+    principal: Principal,
 ) -> AppResult<impl IntoResponse> {
-    auth::check_user(conn, &cookies).await?;
-    let users = user::Entity::find()
-        .order_by_asc(user::Column::Name)
-        .all(conn)
-        .await?
+    principal.id();
+    let mut select = user::Entity::find();
+    if let Some(contest_id) = list.contest_id {
+        select = select.filter(
+            user::Column::Id.in_subquery(
+                Query::select()
+                    .column(privilege::Column::UserId)
+                    .from(privilege::Entity)
+                    .and_where(privilege::Column::ContestId.eq(contest_id.to_string()))
+                    .to_owned(),
+            ),
+        );
+    }
+    let select = list.sort(
+        select,
+        &[("name", user::Column::Name), ("nick", user::Column::Nick)],
+    )?;
+    let (models, total) = list.fetch(select, conn).await?;
+    let users = models
         .into_iter()
         .map(
             |user::Model {
@@ -181,10 +237,10 @@ pub async fn user_list(
                 name,
                 nick,
                 description,
-                banned: banned != 0,
-                root: root != 0,
+                banned,
+                root,
             },
         )
         .collect();
-    Ok(Json(UserListFeedback { users }))
+    Ok(Json(UserListFeedback { users, total }))
 }
@@ -0,0 +1,64 @@
+use crate::utils::{hex, sha256};
+use axum::body::{boxed, Full, HttpBody};
+use axum::headers::{HeaderMapExt, IfModifiedSince, LastModified};
+use axum::http::header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH};
+use axum::http::{HeaderValue, Method, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::time::SystemTime;
+
+pub async fn conditional<B>(req: Request<B>, next: Next<B>) -> Response {
+    if req.method() != Method::GET {
+        return next.run(req).await;
+    }
+    let if_none_match = req
+        .headers()
+        .get(IF_NONE_MATCH)
+        .and_then(|x| x.to_str().ok())
+        .map(|x| x.to_string());
+    let if_modified_since = req.headers().typed_get::<IfModifiedSince>();
+    let response = next.run(req).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+    let (mut parts, mut body) = response.into_parts();
+    parts
+        .headers
+        .insert(CACHE_CONTROL, HeaderValue::from_static("private, no-cache"));
+    let last_modified = parts.headers.typed_get::<LastModified>();
+    if let (None, Some(since), Some(last)) = (&if_none_match, if_modified_since, last_modified) {
+        if !since.is_modified(SystemTime::from(last)) {
+            return (StatusCode::NOT_MODIFIED, parts.headers).into_response();
+        }
+    }
+    // only json bodies are buffered, streamed downloads pass through untouched
+    let json = parts
+        .headers
+        .get(CONTENT_TYPE)
+        .map(|x| x.as_bytes().starts_with(b"application/json"))
+        .unwrap_or(false);
+    if !json {
+        return Response::from_parts(parts, body);
+    }
+    let mut data = Vec::new();
+    while let Some(chunk) = body.data().await {
+        match chunk {
+            Ok(chunk) => data.extend_from_slice(&chunk),
+            Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        }
+    }
+    let etag = format!("\"{}\"", &hex(&sha256(&data))[..32]);
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        parts.headers.insert(ETAG, value);
+    }
+    if let Some(if_none_match) = if_none_match {
+        if if_none_match
+            .split(',')
+            .map(|x| x.trim().trim_start_matches("W/"))
+            .any(|x| x == etag || x == "*")
+        {
+            return (StatusCode::NOT_MODIFIED, parts.headers).into_response();
+        }
+    }
+    Response::from_parts(parts, boxed(Full::from(data)))
+}
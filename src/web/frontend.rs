@@ -0,0 +1,70 @@
+use crate::config;
+use crate::web::prelude::AppError;
+use axum::body::{boxed, Body};
+use axum::http::header::{CACHE_CONTROL, CONTENT_TYPE};
+use axum::http::{HeaderValue, Method, Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use tower::ServiceExt;
+use tower_http::services::{ServeDir, ServeFile};
+
+// serves `web.root`, unknown paths without an extension are routes of the single page app
+pub async fn frontend(req: Request<Body>) -> Response {
+    let path = req.uri().path().to_string();
+    if path == "/api" || path.starts_with("/api/") {
+        return AppError::NotFound(None).into_response();
+    }
+    if req.method() != Method::GET && req.method() != Method::HEAD {
+        return StatusCode::METHOD_NOT_ALLOWED.into_response();
+    }
+    let root = &config().web.root;
+    let mut index = Request::new(Body::empty());
+    *index.method_mut() = req.method().clone();
+    *index.headers_mut() = req.headers().clone();
+    let response = match ServeDir::new(root)
+        .precompressed_br()
+        .precompressed_gzip()
+        .oneshot(req)
+        .await
+    {
+        Ok(response) => response.map(boxed),
+        Err(e) => return AppError::StorageError(e).into_response(),
+    };
+    let route = !path.rsplit('/').next().unwrap_or_default().contains('.');
+    let response = if response.status() == StatusCode::NOT_FOUND && route {
+        match ServeFile::new(format!("{}/index.html", root))
+            .precompressed_br()
+            .precompressed_gzip()
+            .oneshot(index)
+            .await
+        {
+            Ok(response) => response.map(boxed),
+            Err(e) => return AppError::StorageError(e).into_response(),
+        }
+    } else {
+        response
+    };
+    cache(&path, response)
+}
+
+// html is revalidated so a deploy shows up at once, hashed bundles under `/assets` never change
+fn cache(path: &str, mut response: Response) -> Response {
+    if !response.status().is_success() {
+        return response;
+    }
+    let html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .map(|x| x.as_bytes().starts_with(b"text/html"))
+        .unwrap_or(false);
+    let value = if html {
+        "no-cache"
+    } else if path.starts_with("/assets/") {
+        "public, max-age=31536000, immutable"
+    } else {
+        "public, max-age=3600"
+    };
+    response
+        .headers_mut()
+        .insert(CACHE_CONTROL, HeaderValue::from_static(value));
+    response
+}
@@ -0,0 +1,100 @@
+use crate::config;
+use crate::judger::self_test;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use schemars::JsonSchema;
+use sea_orm::{ConnectionTrait, DatabaseConnection, Statement};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::os::unix::fs::PermissionsExt;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+// the sandbox self-test forks a compiler, so probes reuse its outcome for a while
+const SELF_TEST_TTL: Duration = Duration::from_secs(60);
+
+static SELF_TEST: Mutex<Option<(Instant, Result<(), String>)>> = Mutex::const_new(None);
+
+#[derive(Serialize, JsonSchema)]
+pub struct ReadyFeedback {
+    pub ready: bool,
+    pub checks: BTreeMap<String, String>,
+}
+
+pub async fn healthz() -> impl IntoResponse {
+    "ok"
+}
+
+pub async fn checks(conn: &DatabaseConnection) -> BTreeMap<String, Result<(), String>> {
+    let mut checks = BTreeMap::new();
+    checks.insert("database".to_string(), database(conn).await);
+    checks.insert(
+        "storage".to_string(),
+        tokio::task::spawn_blocking(storage)
+            .await
+            .unwrap_or_else(|e| Err(e.to_string())),
+    );
+    checks.insert("compilers".to_string(), compilers());
+    checks.insert("sandbox".to_string(), sandbox().await);
+    checks
+}
+
+pub async fn readyz(Extension(ref conn): Extension<DatabaseConnection>) -> impl IntoResponse {
+    let checks = checks(conn).await;
+    let ready = checks.values().all(|x| x.is_ok());
+    let feedback = ReadyFeedback {
+        ready,
+        checks: checks
+            .into_iter()
+            .map(|(k, v)| (k, v.err().unwrap_or_else(|| "ok".to_string())))
+            .collect(),
+    };
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(feedback))
+}
+
+async fn database(conn: &DatabaseConnection) -> Result<(), String> {
+    let statement = Statement::from_string(conn.get_database_backend(), "SELECT 1".to_string());
+    conn.execute(statement)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn storage() -> Result<(), String> {
+    let path = format!("{}/.readyz", config().judger.root);
+    std::fs::write(&path, b"ok").map_err(|e| format!("`{}` is not writable: {}", path, e))?;
+    let _ = std::fs::remove_file(&path);
+    Ok(())
+}
+
+fn compilers() -> Result<(), String> {
+    for path in [&config().judger.compiler_c, &config().judger.compiler_cxx] {
+        match std::fs::metadata(path) {
+            Ok(x) if x.is_file() && x.permissions().mode() & 0o111 != 0 => {}
+            _ => return Err(format!("`{}` is not an executable", path)),
+        }
+    }
+    Ok(())
+}
+
+async fn sandbox() -> Result<(), String> {
+    // held across the test, so concurrent probes never share its workspace
+    let mut cached = SELF_TEST.lock().await;
+    if let Some((time, ref result)) = *cached {
+        if time.elapsed() < SELF_TEST_TTL {
+            return result.clone();
+        }
+    }
+    let result = tokio::task::spawn_blocking(self_test)
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|x| x.map_err(|e| e.to_string()));
+    *cached = Some((Instant::now(), result.clone()));
+    result
+}
@@ -0,0 +1,93 @@
+use crate::web::prelude::*;
+use axum::async_trait;
+use axum::extract::{FromRequest, Query, RequestParts};
+use schemars::JsonSchema;
+use sea_orm::prelude::*;
+use sea_orm::{
+    Iterable, Order, PaginatorTrait, PrimaryKeyToColumn, QueryOrder, QuerySelect, Select,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+pub const LIMIT_DEFAULT: u64 = 100;
+pub const LIMIT_MAX: u64 = 1000;
+
+#[derive(Deserialize, Default, JsonSchema)]
+pub struct ListQuery {
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+    pub sort: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub problem_id: Option<Uuid>,
+    pub contest_id: Option<Uuid>,
+    pub status: Option<String>,
+    pub language: Option<String>,
+    pub action: Option<String>,
+    pub target: Option<Uuid>,
+    pub since: Option<DateTime>,
+    pub until: Option<DateTime>,
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for ListQuery {
+    type Rejection = AppError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<ListQuery>::from_request(req).await.good()?;
+        Ok(query)
+    }
+}
+
+impl ListQuery {
+    pub fn range<E: EntityTrait>(&self, mut select: Select<E>, column: E::Column) -> Select<E> {
+        if let Some(since) = self.since {
+            select = select.filter(column.gte(since));
+        }
+        if let Some(until) = self.until {
+            select = select.filter(column.lt(until));
+        }
+        select
+    }
+
+    // the first column is the default, a leading `-` sorts descending
+    pub fn sort<E: EntityTrait>(
+        &self,
+        select: Select<E>,
+        columns: &[(&str, E::Column)],
+    ) -> AppResult<Select<E>> {
+        let sort = self.sort.as_deref().unwrap_or(columns[0].0);
+        let (name, order) = match sort.strip_prefix('-') {
+            Some(x) => (x, Order::Desc),
+            None => (sort, Order::Asc),
+        };
+        let column = columns
+            .iter()
+            .find(|x| x.0 == name)
+            .map(|x| x.1)
+            .ok_or_else(|| {
+                AppError::BadRequest(Some(anyhow::anyhow!("cannot sort by `{}`", name)))
+            })?;
+        let mut select = select.order_by(column, order);
+        for key in E::PrimaryKey::iter() {
+            select = select.order_by_asc(key.into_column());
+        }
+        Ok(select)
+    }
+
+    pub async fn fetch<E: EntityTrait>(
+        &self,
+        select: Select<E>,
+        conn: &DatabaseConnection,
+    ) -> AppResult<(Vec<E::Model>, u64)>
+    where
+        E::Model: Sync,
+    {
+        let total = select.clone().count(conn).await? as u64;
+        let models = select
+            .offset(self.offset.unwrap_or(0))
+            .limit(self.limit.unwrap_or(LIMIT_DEFAULT).min(LIMIT_MAX))
+            .all(conn)
+            .await?;
+        Ok((models, total))
+    }
+}
@@ -0,0 +1,34 @@
+use crate::metrics::metrics;
+use axum::extract::MatchedPath;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::time::Instant;
+
+// labelled by the route pattern rather than the path, so ids do not blow up the label set
+pub async fn track<B>(req: Request<B>, next: Next<B>) -> Response {
+    let route = match req.extensions().get::<MatchedPath>() {
+        Some(x) => x.as_str().to_string(),
+        None => return next.run(req).await,
+    };
+    let method = req.method().to_string();
+    let start = Instant::now();
+    let response = next.run(req).await;
+    metrics()
+        .http_duration
+        .with_label_values(&[&method, &route])
+        .observe(start.elapsed().as_secs_f64());
+    metrics()
+        .http_requests
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .inc();
+    response
+}
+
+pub async fn metrics_read() -> impl IntoResponse {
+    (
+        [(CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        metrics().encode(),
+    )
+}
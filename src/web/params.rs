@@ -0,0 +1,43 @@
+use crate::web::prelude::*;
+use axum::async_trait;
+use axum::body::HttpBody;
+use axum::extract::{FromRequest, Path, RequestParts};
+use axum::http::header::CONTENT_TYPE;
+use axum::{BoxError, Json};
+use serde::de::DeserializeOwned;
+
+// reads parameters from the path and the query string, falling back to a json body
+pub struct Params<T>(pub T);
+
+#[async_trait]
+impl<T, B> FromRequest<B> for Params<T>
+where
+    T: DeserializeOwned,
+    B: HttpBody + Send,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let mut pairs = match Path::<Vec<(String, String)>>::from_request(req).await {
+            Ok(Path(x)) => x,
+            Err(_) => vec![],
+        };
+        if let Some(query) = req.uri().query() {
+            pairs.extend(serde_urlencoded::from_str::<Vec<(String, String)>>(query).good()?);
+        }
+        let json = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|x| x.to_str().ok())
+            .map(|x| x.starts_with("application/json"))
+            .unwrap_or(false);
+        if pairs.is_empty() && json {
+            let Json(payload) = Json::<T>::from_request(req).await.good()?;
+            return Ok(Params(payload));
+        }
+        let query = serde_urlencoded::to_string(&pairs).good()?;
+        Ok(Params(serde_urlencoded::from_str(&query).good()?))
+    }
+}
@@ -1,8 +1,10 @@
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::response::Response;
+use axum::Json;
+use schemars::JsonSchema;
 use sea_orm::DbErr;
-use serde_json::json;
+use serde::Serialize;
 use thiserror::Error;
 
 pub use crate::uuid;
@@ -20,67 +22,63 @@ pub enum AppError {
     NotFound(Option<anyhow::Error>),
     #[error("BadRequest")]
     BadRequest(Option<anyhow::Error>),
+    #[error("Unavailable")]
+    Unavailable(Option<anyhow::Error>),
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorType {
+    DatabaseError,
+    StorageError,
+    Forbidden,
+    NotFound,
+    BadRequest,
+    Unavailable,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ErrorFeedback {
+    #[serde(rename = "type")]
+    pub kind: ErrorType,
+    pub message: String,
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        match self {
-            AppError::DatabaseError(e) => {
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    json!({
-                        "type": "database_error",
-                        "message": format!("{}", e),
-                    })
-                    .to_string(),
-                )
-                    .into_response()
-            }
-            AppError::StorageError(e) => {
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    json!({
-                        "type": "storage_error",
-                        "message": format!("{}", e),
-                    })
-                    .to_string(),
-                )
-                    .into_response()
-            }
-            AppError::Forbidden(e) => {
-                return (
-                    StatusCode::FORBIDDEN,
-                    json!({
-                        "type": "forbidden",
-                        "message": format!("{:?}", e),
-                    })
-                    .to_string(),
-                )
-                    .into_response()
-            }
-            AppError::NotFound(e) => {
-                return (
-                    StatusCode::NOT_FOUND,
-                    json!({
-                        "type": "not_found",
-                        "message": format!("{:?}", e),
-                    })
-                    .to_string(),
-                )
-                    .into_response()
-            }
-            AppError::BadRequest(e) => {
-                return (
-                    StatusCode::BAD_REQUEST,
-                    json!({
-                        "type": "bad_request",
-                        "message": format!("{:?}", e),
-                    })
-                    .to_string(),
-                )
-                    .into_response()
-            }
-        }
+        let (status, kind, message) = match self {
+            AppError::DatabaseError(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ErrorType::DatabaseError,
+                format!("{}", e),
+            ),
+            AppError::StorageError(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ErrorType::StorageError,
+                format!("{}", e),
+            ),
+            AppError::Forbidden(e) => (
+                StatusCode::FORBIDDEN,
+                ErrorType::Forbidden,
+                format!("{:?}", e),
+            ),
+            AppError::NotFound(e) => (
+                StatusCode::NOT_FOUND,
+                ErrorType::NotFound,
+                format!("{:?}", e),
+            ),
+            AppError::BadRequest(e) => (
+                StatusCode::BAD_REQUEST,
+                ErrorType::BadRequest,
+                format!("{:?}", e),
+            ),
+            AppError::Unavailable(e) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                ErrorType::Unavailable,
+                format!("{:?}", e),
+            ),
+        };
+        (status, Json(ErrorFeedback { kind, message })).into_response()
     }
 }
 